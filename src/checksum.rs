@@ -0,0 +1,111 @@
+#[cfg(feature = "cool")]
+use crate::ConflictPolicy;
+use crate::HvacService;
+#[cfg(feature = "fan")]
+use crate::ServiceFanConfig;
+
+const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// 32-bit FNV-1a hash fed explicit little-endian encodings, so it is independent of platform
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub(crate) struct Fnv1a(u32);
+
+impl Fnv1a {
+    pub(crate) fn new() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u32::from(byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub(crate) fn bool(&mut self, value: bool) {
+        self.bytes(&[u8::from(value)]);
+    }
+
+    #[cfg(any(
+        feature = "fan",
+        feature = "cool",
+        feature = "setpoint",
+        feature = "stats"
+    ))]
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.bytes(&[value]);
+    }
+
+    pub(crate) fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    #[cfg(any(feature = "setpoint", all(feature = "fan", feature = "cool")))]
+    pub(crate) fn i16(&mut self, value: i16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    #[cfg(any(feature = "setpoint", feature = "stats"))]
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    /// `usize` widened to 64 bits, so it hashes the same on every platform
+    #[cfg(any(feature = "setpoint", feature = "stats"))]
+    pub(crate) fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    /// `None` as a 0 tag, or `Some` as a 1 tag followed by the value encoded by `encode`
+    pub(crate) fn option<T>(&mut self, value: Option<T>, encode: impl FnOnce(&mut Self, T)) {
+        match value {
+            Some(value) => {
+                self.bytes(&[1]);
+                encode(self, value);
+            }
+            None => self.bytes(&[0]),
+        };
+    }
+
+    /// `None` as a 0 tag, or `Some` as a 1 tag followed by the value
+    pub(crate) fn option_u32(&mut self, value: Option<u32>) {
+        self.option(value, Self::u32);
+    }
+
+    /// `None` as 0, heat as 1, and cool as 2
+    pub(crate) fn service(&mut self, service: Option<HvacService>) {
+        self.bytes(&[match service {
+            None => 0,
+            Some(HvacService::Heat) => 1,
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => 2,
+        }]);
+    }
+
+    /// last wins as 0, heat priority as 1, cool priority as 2, and ignore as 3
+    #[cfg(feature = "cool")]
+    pub(crate) fn conflict_policy(&mut self, policy: ConflictPolicy) {
+        self.bytes(&[match policy {
+            ConflictPolicy::LastWins => 0,
+            ConflictPolicy::HeatPriority => 1,
+            ConflictPolicy::CoolPriority => 2,
+            ConflictPolicy::Ignore => 3,
+        }]);
+    }
+
+    /// coupled, then the purge and pre-purge seconds
+    #[cfg(feature = "fan")]
+    pub(crate) fn fan_config(&mut self, config: ServiceFanConfig) {
+        self.bool(config.coupled);
+        self.u32(config.purge_seconds);
+        self.u32(config.prepurge_seconds);
+    }
+
+    pub(crate) fn finish(self) -> u32 {
+        self.0
+    }
+}
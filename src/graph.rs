@@ -0,0 +1,172 @@
+/// logical operating state of the state machine, coarser than its timers
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LogicalState {
+    /// no service and fan off
+    Idle,
+    /// heat service active
+    Heating,
+    /// cool service active
+    #[cfg(feature = "cool")]
+    Cooling,
+    /// fan on with no service active, either manually, economizing, or pre-warming
+    #[cfg(feature = "fan")]
+    FanOnly,
+    /// fan on in auto mode after a service stopped, held by its min run time or coil overrun
+    #[cfg(feature = "fan")]
+    FanPurge,
+}
+
+/// allowed edge between two logical states
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateTransition {
+    /// state the transition leaves
+    pub from: LogicalState,
+    /// state the transition enters
+    pub to: LogicalState,
+    /// human readable condition under which the transition is taken
+    pub guard: &'static str,
+}
+
+const fn edge(from: LogicalState, to: LogicalState, guard: &'static str) -> StateTransition {
+    StateTransition { from, to, guard }
+}
+
+pub(crate) const STATES: &[LogicalState] = &[
+    LogicalState::Idle,
+    LogicalState::Heating,
+    #[cfg(feature = "cool")]
+    LogicalState::Cooling,
+    #[cfg(feature = "fan")]
+    LogicalState::FanOnly,
+    #[cfg(feature = "fan")]
+    LogicalState::FanPurge,
+];
+
+#[cfg(feature = "cool")]
+const START_HEAT: &str = "heat called and its recover, changeover delay, and auto hold elapsed";
+#[cfg(not(feature = "cool"))]
+const START_HEAT: &str = "heat called and its recover elapsed";
+#[cfg(feature = "cool")]
+const START_COOL: &str = "cool called, not locked out or economizing, and its recover, changeover \
+                          delay, and auto hold elapsed";
+#[cfg(feature = "cool")]
+const HEAT_TO_COOL: &str = "cool called with heat min run elapsed and no changeover delay";
+#[cfg(feature = "cool")]
+const COOL_TO_HEAT: &str = "heat called with cool min run elapsed and no changeover delay";
+
+#[cfg(not(feature = "fan"))]
+pub(crate) const TRANSITIONS: &[StateTransition] = &[
+    edge(LogicalState::Idle, LogicalState::Heating, START_HEAT),
+    #[cfg(feature = "cool")]
+    edge(LogicalState::Idle, LogicalState::Cooling, START_COOL),
+    edge(
+        LogicalState::Heating,
+        LogicalState::Idle,
+        "heat no longer called and its min run elapsed",
+    ),
+    #[cfg(feature = "cool")]
+    edge(
+        LogicalState::Cooling,
+        LogicalState::Idle,
+        "cool no longer called and its min run elapsed",
+    ),
+    #[cfg(feature = "cool")]
+    edge(LogicalState::Heating, LogicalState::Cooling, HEAT_TO_COOL),
+    #[cfg(feature = "cool")]
+    edge(LogicalState::Cooling, LogicalState::Heating, COOL_TO_HEAT),
+];
+
+#[cfg(feature = "fan")]
+const FAN_START: &str = "fan set to manual, economizing, or pre-warming, and its recover elapsed";
+#[cfg(feature = "fan")]
+const FAN_STOP: &str = "fan in auto, not economizing or pre-warming, and its min run elapsed";
+#[cfg(all(feature = "fan", feature = "cool"))]
+const START_HEAT_WITH_FAN: &str = "heat called and its recover, changeover delay, auto hold, and \
+                                   start stagger elapsed";
+#[cfg(all(feature = "fan", not(feature = "cool")))]
+const START_HEAT_WITH_FAN: &str = "heat called and its recover and start stagger elapsed";
+#[cfg(all(feature = "fan", feature = "cool"))]
+const START_COOL_WITH_FAN: &str = "cool called, not locked out or economizing, and its recover, \
+                                   changeover delay, auto hold, and start stagger elapsed";
+
+#[cfg(feature = "fan")]
+pub(crate) const TRANSITIONS: &[StateTransition] = &[
+    edge(LogicalState::Idle, LogicalState::Heating, START_HEAT),
+    #[cfg(feature = "cool")]
+    edge(LogicalState::Idle, LogicalState::Cooling, START_COOL),
+    edge(LogicalState::Idle, LogicalState::FanOnly, FAN_START),
+    edge(
+        LogicalState::Heating,
+        LogicalState::Idle,
+        "heat no longer called, its min run elapsed, and the fan may stop",
+    ),
+    #[cfg(feature = "cool")]
+    edge(
+        LogicalState::Cooling,
+        LogicalState::Idle,
+        "cool no longer called, its min run elapsed, and the fan may stop",
+    ),
+    #[cfg(feature = "cool")]
+    edge(LogicalState::Heating, LogicalState::Cooling, HEAT_TO_COOL),
+    #[cfg(feature = "cool")]
+    edge(LogicalState::Cooling, LogicalState::Heating, COOL_TO_HEAT),
+    edge(
+        LogicalState::Heating,
+        LogicalState::FanOnly,
+        "heat no longer called and its min run elapsed with the fan manual",
+    ),
+    #[cfg(feature = "cool")]
+    edge(
+        LogicalState::Cooling,
+        LogicalState::FanOnly,
+        "cool no longer called and its min run elapsed with the fan manual or economizing",
+    ),
+    edge(
+        LogicalState::Heating,
+        LogicalState::FanPurge,
+        "heat no longer called and its min run elapsed with the fan min run remaining",
+    ),
+    #[cfg(feature = "cool")]
+    edge(
+        LogicalState::Cooling,
+        LogicalState::FanPurge,
+        "cool no longer called and its min run elapsed with the fan min run or coil overrun \
+         remaining",
+    ),
+    edge(LogicalState::FanPurge, LogicalState::Idle, FAN_STOP),
+    edge(LogicalState::FanOnly, LogicalState::Idle, FAN_STOP),
+    edge(
+        LogicalState::FanOnly,
+        LogicalState::FanPurge,
+        "fan returned to auto with its min run remaining",
+    ),
+    edge(
+        LogicalState::FanPurge,
+        LogicalState::FanOnly,
+        "fan set to manual, economizing, or pre-warming",
+    ),
+    edge(
+        LogicalState::FanOnly,
+        LogicalState::Heating,
+        START_HEAT_WITH_FAN,
+    ),
+    #[cfg(feature = "cool")]
+    edge(
+        LogicalState::FanOnly,
+        LogicalState::Cooling,
+        START_COOL_WITH_FAN,
+    ),
+    edge(
+        LogicalState::FanPurge,
+        LogicalState::Heating,
+        START_HEAT_WITH_FAN,
+    ),
+    #[cfg(feature = "cool")]
+    edge(
+        LogicalState::FanPurge,
+        LogicalState::Cooling,
+        START_COOL_WITH_FAN,
+    ),
+];
@@ -0,0 +1,98 @@
+use crate::checksum::Fnv1a;
+use crate::HvacService;
+
+const HISTORY_LEN: usize = 16;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct RunInterval {
+    service: HvacService,
+    start: u32,
+    stop: Option<u32>,
+}
+
+/// fixed-size ring buffer of the most recent service run intervals
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) struct RunHistory {
+    intervals: [Option<RunInterval>; HISTORY_LEN],
+    next: usize,
+}
+
+impl RunHistory {
+    /// record `service` starting at `seconds`
+    pub(crate) fn start(&mut self, service: HvacService, seconds: u32) {
+        self.intervals[self.next] = Some(RunInterval {
+            service,
+            start: seconds,
+            stop: None,
+        });
+        self.next = (self.next + 1) % HISTORY_LEN;
+    }
+
+    /// record the most recently started service stopping at `seconds`
+    pub(crate) fn stop(&mut self, seconds: u32) {
+        if let Some(interval) = &mut self.intervals[(self.next + HISTORY_LEN - 1) % HISTORY_LEN] {
+            if interval.stop.is_none() {
+                interval.stop = Some(seconds);
+            };
+        };
+    }
+
+    /// start of the most recent interval if it is `service` and has not stopped
+    pub(crate) fn running_since(&self, service: HvacService) -> Option<u32> {
+        self.intervals[(self.next + HISTORY_LEN - 1) % HISTORY_LEN]
+            .filter(|interval| interval.service == service && interval.stop.is_none())
+            .map(|interval| interval.start)
+    }
+
+    /// number of times `service` started at or after `from`, counting at most the whole history
+    pub(crate) fn starts_since(&self, service: HvacService, from: u32) -> usize {
+        self.intervals
+            .iter()
+            .flatten()
+            .filter(|interval| interval.service == service && interval.start >= from)
+            .count()
+    }
+
+    /// earliest start of `service` at or after `from`, if any
+    pub(crate) fn oldest_start_since(&self, service: HvacService, from: u32) -> Option<u32> {
+        self.intervals
+            .iter()
+            .flatten()
+            .filter(|interval| interval.service == service && interval.start >= from)
+            .map(|interval| interval.start)
+            .min()
+    }
+
+    /// feed every interval slot in storage order, then the write position
+    pub(crate) fn hash(&self, hash: &mut Fnv1a) {
+        for &interval in self.intervals.iter() {
+            hash.option(interval, |hash, interval| {
+                hash.service(Some(interval.service));
+                hash.u32(interval.start);
+                hash.option_u32(interval.stop);
+            });
+        }
+        hash.usize(self.next);
+    }
+
+    /// seconds `service` was active between `from` and `to`, or `None` if the history no longer
+    /// covers the whole span
+    pub(crate) fn active_seconds(&self, service: HvacService, from: u32, to: u32) -> Option<u32> {
+        if let Some(oldest) = self.intervals[self.next] {
+            if oldest.start > from {
+                return None;
+            };
+        };
+        let mut seconds = 0u32;
+        for interval in self.intervals.iter().flatten() {
+            if interval.service == service {
+                let start = interval.start.max(from);
+                let stop = interval.stop.unwrap_or(to).min(to);
+                seconds = seconds.saturating_add(stop.saturating_sub(start));
+            };
+        }
+        Some(seconds)
+    }
+}
@@ -10,13 +10,35 @@
 //! constraints on the minimum run and recovery time are supported for the heat, cool, and fan
 //! services.
 //!
-//! This crate has no dependencies on the standard library or any other crates, making it
-//! easily used in standard applications as well as embedded targets leveraging
+//! This crate has no dependencies on the standard library or, by default, any other crates, making
+//! it easily used in standard applications as well as embedded targets leveraging
 //! [`#![no_std]`](https://doc.rust-lang.org/reference/attributes.html?highlight=no_std#crate-only-attributes).
+//!
+//! Enabling the `defmt` feature derives `defmt::Format` on the public types for logging on embedded
+//! targets.
+//!
+//! The default `fan` feature provides the fan output along with everything coupled to it, such as
+//! the economizer, start stagger, and fan speed. Disabling it for installs without an air handler,
+//! such as boilers, removes them from the state machine and its api entirely.
+//!
+//! The default `cool` feature provides the cool service along with everything built on it, such as
+//! changeover, the reversing valve, the economizer, and humidity lockout. Disabling it leaves a
+//! heat-only controller.
+//!
+//! The default `setpoint` feature provides scheduled setpoint recovery, occupancy, and the
+//! temperature filter, `erv` the energy recovery ventilator output, `zone` the zone aggregator, and
+//! `simulate` the `simulate` fn and its `SimStats`.
+//!
+//! The default `stats` feature accumulates the runtime and cycle counters taken by
+//! `take_runtime_stats`, along with the run history, blocked time, and short cycling and capacity
+//! warnings. Disabling it along with the other optional features trims the controller to heat
+//! alone for the smallest targets.
 
 //!
 //! # Example
 //! ```
+//! # #[cfg(all(feature = "fan", feature = "cool"))]
+//! # {
 //! use hvac::prelude::*;
 //!
 //! // create a new hvac controller with the
@@ -108,6 +130,7 @@
 //! // into auto mode
 //! let state = hvac_controller.fan_auto(true);
 //! assert_eq!(state.fan, false);
+//! # }
 //! ```
 #![no_std]
 #![deny(warnings)]
@@ -131,80 +154,1141 @@
 #![deny(unused_qualifications)]
 #![deny(unused_results)]
 #![deny(variant_size_differences)]
-#![cfg_attr(feature = "cargo-clippy", deny(clippy::all))]
+#![deny(clippy::all)]
+
+mod checksum;
+mod graph;
+#[cfg(feature = "stats")]
+mod history;
+#[cfg(feature = "setpoint")]
+mod recovery;
+#[cfg(feature = "stats")]
+mod runtime;
+mod simulate;
+#[cfg(feature = "zone")]
+mod zone;
+
+use crate::checksum::Fnv1a;
+pub use crate::graph::{LogicalState, StateTransition};
+#[cfg(feature = "stats")]
+use crate::history::RunHistory;
+#[cfg(feature = "setpoint")]
+use crate::recovery::{ScheduledSetpoint, TemperatureHistory};
+#[cfg(feature = "stats")]
+pub use crate::runtime::RuntimeStats;
+pub use crate::simulate::HvacCommand;
+#[cfg(feature = "simulate")]
+pub use crate::simulate::{simulate, SimStats};
+#[cfg(feature = "zone")]
+pub use crate::zone::{ZoneAggregator, ZoneStrategy};
 
 /// hvac services
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum HvacService {
     /// heat
     Heat,
     /// cool
+    #[cfg(feature = "cool")]
+    Cool,
+}
+
+#[cfg(feature = "cool")]
+impl HvacService {
+    /// the opposing service, heat for cool and cool for heat
+    pub fn opposite(self) -> HvacService {
+        match self {
+            HvacService::Heat => HvacService::Cool,
+            HvacService::Cool => HvacService::Heat,
+        }
+    }
+
+    /// if a reversing valve wired to `valve` is energized while this service is active
+    pub fn reversing_valve_energized(self, valve: ReversingValve) -> bool {
+        match valve {
+            ReversingValve::O => self == HvacService::Cool,
+            ReversingValve::B => self == HvacService::Heat,
+        }
+    }
+}
+
+/// heat pump reversing valve orientation, named for its thermostat terminal
+#[cfg(feature = "cool")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReversingValve {
+    /// energized in cool, as most heat pumps expect
+    O,
+    /// energized in heat
+    B,
+}
+
+/// how simultaneous calls for heat and cool are resolved
+///
+/// the policy picks the call to serve before any timer is considered, so when both calls are
+/// present and both services' wait timers clear on the same tick, the service the policy serves
+/// starts and the other remains unserved. with `LastWins` only the latest call is present, and
+/// otherwise an unserved call is held by `BlockReason::ConflictPolicy`.
+#[cfg(feature = "cool")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConflictPolicy {
+    /// a call for one service disables any call for the other
+    LastWins,
+    /// calls for both services are kept, with heat served while both are present
+    HeatPriority,
+    /// calls for both services are kept, with cool served while both are present
+    CoolPriority,
+    /// calls for both services are kept, with neither served while both are present
+    Ignore,
+}
+
+/// reason a called-for service is being held off
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BlockReason {
+    /// service is waiting on its own minimum recover (or post run off) time
+    MinRecover,
+    /// opposing service is active and waiting on its minimum run time
+    #[cfg(feature = "cool")]
+    OpposingMinRun,
+    /// fan must start with the service but is waiting on its minimum recover time
+    #[cfg(feature = "fan")]
+    FanMinRecover,
+    /// call for cool is being served by the economizer
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    Economizer,
+    /// fan has just started and the service is deferred to stagger their inrush current
+    #[cfg(feature = "fan")]
+    StartStagger,
+    /// a changeover between heat and cool occurred too recently
+    #[cfg(feature = "cool")]
+    AutoHold,
+    /// opposing service stopped too recently to change over
+    #[cfg(feature = "cool")]
+    ChangeoverDelay,
+    /// call for cool is locked out because relative humidity is below its threshold
+    #[cfg(feature = "cool")]
+    HumidityLockout,
+    /// every service is shut down until the emergency stop is cleared
+    EmergencyStop,
+    /// the system is powered off
+    PoweredOff,
+    /// services are held off until the startup delay elapses
+    StartupDelay,
+    /// call has not yet persisted for the call debounce time or the service's min call duration
+    CallDebounce,
+    /// reversing valve has changed over and is settling before the compressor starts
+    #[cfg(feature = "cool")]
+    ValveSettle,
+    /// call is suppressed by the conflict policy while heat and cool are both called
+    #[cfg(feature = "cool")]
+    ConflictPolicy,
+}
+
+/// timed gate that may hold off a service, see `Hvac::binding_constraint`
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConstraintKind {
+    /// service's own minimum recover (or post run off) time
+    MinRecover,
+    /// opposing service's remaining minimum run time
+    #[cfg(feature = "cool")]
+    OpposingMinRun,
+    /// off time required between the opposing service and this one
+    #[cfg(feature = "cool")]
+    ChangeoverDelay,
+    /// hold after a changeover between heat and cool
+    #[cfg(feature = "cool")]
+    AutoHold,
+    /// recover time of a compressor shared by heat and cool
+    #[cfg(feature = "cool")]
+    SharedCompressorRecover,
+    /// delay after the first tick
+    StartupDelay,
+    /// time for a new call to persist, the longer of the call debounce and min call duration
+    CallDebounce,
+    /// reversing valve settling after a changeover
+    #[cfg(feature = "cool")]
+    ValveSettle,
+    /// fan minimum recover time, for a service that starts the fan
+    #[cfg(feature = "fan")]
+    FanMinRecover,
+    /// stagger between the fan and service starting
+    #[cfg(feature = "fan")]
+    StartStagger,
+}
+
+/// timer tracked by the state machine
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TimerKind {
+    /// heat min run or min recover
+    Heat,
+    /// cool min run, min recover, or post run off
+    #[cfg(feature = "cool")]
     Cool,
+    /// fan min run or min recover
+    #[cfg(feature = "fan")]
+    Fan,
+    /// energy recovery ventilator min run or min recover
+    #[cfg(feature = "erv")]
+    Erv,
+}
+
+/// what a tick did, or what it was prevented from doing
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TickOutcome {
+    /// outputs did not change and nothing is being held
+    Unchanged,
+    /// outputs changed
+    Changed,
+    /// service is no longer called for but is held on by its minimum run time for the given
+    /// seconds remaining
+    HeldByMinRun(HvacService, u32),
+    /// fan is no longer wanted but is held on by its minimum run time for the given seconds
+    /// remaining
+    #[cfg(feature = "fan")]
+    FanHeldByMinRun(u32),
+}
+
+/// inconsistency found in an hvac controller configuration
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HvacConfigError {
+    /// a heat constraint of `u32::MAX` seconds can never elapse since time saturates there
+    HeatConstraintNeverElapses,
+    /// a cool constraint of `u32::MAX` seconds can never elapse since time saturates there
+    #[cfg(feature = "cool")]
+    CoolConstraintNeverElapses,
+    /// a fan constraint of `u32::MAX` seconds can never elapse since time saturates there
+    #[cfg(feature = "fan")]
+    FanConstraintNeverElapses,
+    /// a changeover delay or auto hold of `u32::MAX` seconds can never elapse
+    #[cfg(feature = "cool")]
+    ChangeoverDelayNeverElapses,
+    /// an energy recovery ventilator constraint of `u32::MAX` seconds can never elapse
+    #[cfg(feature = "erv")]
+    ErvConstraintNeverElapses,
+    /// a shared compressor recover time of `u32::MAX` seconds can never elapse
+    #[cfg(feature = "cool")]
+    SharedCompressorRecoverNeverElapses,
+    /// a startup delay of `u32::MAX` seconds can never elapse
+    StartupDelayNeverElapses,
+    /// a call debounce or min call duration of `u32::MAX` seconds can never elapse
+    CallDebounceNeverElapses,
+    /// a valve settle time of `u32::MAX` seconds can never elapse
+    #[cfg(feature = "cool")]
+    ValveSettleNeverElapses,
+    /// a circulation period of zero, or with on and off times overflowing `u32::MAX` seconds,
+    /// never cycles
+    #[cfg(feature = "fan")]
+    CirculateNeverCycles,
+    /// an economizer yield of `u32::MAX` seconds can never elapse
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    EconomizerYieldNeverElapses,
+    /// a freeze protection pausing below a temperature above the one it resumes above
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    FreezeProtectionInverted,
+    /// a time scale with a numerator of zero and a nonzero denominator stops time
+    TimeScaleStopsTime,
+}
+
+impl core::fmt::Display for HvacConfigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            HvacConfigError::HeatConstraintNeverElapses => "heat constraint can never elapse",
+            #[cfg(feature = "cool")]
+            HvacConfigError::CoolConstraintNeverElapses => "cool constraint can never elapse",
+            #[cfg(feature = "fan")]
+            HvacConfigError::FanConstraintNeverElapses => "fan constraint can never elapse",
+            #[cfg(feature = "cool")]
+            HvacConfigError::ChangeoverDelayNeverElapses => "changeover delay can never elapse",
+            #[cfg(feature = "erv")]
+            HvacConfigError::ErvConstraintNeverElapses => "erv constraint can never elapse",
+            #[cfg(feature = "cool")]
+            HvacConfigError::SharedCompressorRecoverNeverElapses => {
+                "shared compressor recover can never elapse"
+            }
+            HvacConfigError::StartupDelayNeverElapses => "startup delay can never elapse",
+            HvacConfigError::CallDebounceNeverElapses => "call debounce can never elapse",
+            #[cfg(feature = "cool")]
+            HvacConfigError::ValveSettleNeverElapses => "valve settle can never elapse",
+            #[cfg(feature = "fan")]
+            HvacConfigError::CirculateNeverCycles => "circulation never cycles",
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            HvacConfigError::EconomizerYieldNeverElapses => "economizer yield can never elapse",
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            HvacConfigError::FreezeProtectionInverted => "freeze protection thresholds inverted",
+            HvacConfigError::TimeScaleStopsTime => "time scale stops time",
+        })
+    }
 }
 
 /// hvac state
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct HvacState {
     /// active service, if any
     pub service: Option<HvacService>,
     /// if fan is active
+    #[cfg(feature = "fan")]
+    pub fan: bool,
+    /// if fan is active to economize (cool with outside air) with the compressor suppressed
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub economizing: bool,
+    /// if cool is paused with the fan running to de-ice its coil, see
+    /// `Hvac::with_freeze_protection`
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub freeze_protecting: bool,
+    /// if energy recovery ventilator is active
+    #[cfg(feature = "erv")]
+    pub erv: bool,
+    /// if the system is powered on and will respond to calls, see `Hvac::power_off`
+    pub powered: bool,
+    /// if a service is starting often enough to suggest a fault, see
+    /// `Hvac::with_short_cycle_warning`
+    #[cfg(feature = "stats")]
+    pub short_cycling: bool,
+    /// if a service has run continuously while called for long enough to suggest it cannot meet
+    /// the load, see `Hvac::with_capacity_warning`
+    #[cfg(feature = "stats")]
+    pub capacity_warning: bool,
+    /// if ticks arrive too far apart to honor the configured constraints precisely, see
+    /// `Hvac::with_tick_interval`
+    pub undersampled: bool,
+    /// if services are held off by the startup delay, see `Hvac::with_startup_delay`
+    pub starting_up: bool,
+    /// if the reversing valve has changed over and the compressor is held off while it settles,
+    /// see `Hvac::with_valve_settle`
+    #[cfg(feature = "cool")]
+    pub valve_settling: bool,
+}
+
+/// how the fan runs with a service, see `Hvac::with_heat_fan_config`
+#[cfg(feature = "fan")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ServiceFanConfig {
+    /// fan starts and stops with the service
+    pub coupled: bool,
+    /// seconds a running fan stays on after the service stops
+    pub purge_seconds: u32,
+    /// seconds the fan runs before the service starts, if the fan starts with it
+    pub prepurge_seconds: u32,
+}
+
+#[cfg(feature = "fan")]
+impl Default for ServiceFanConfig {
+    fn default() -> Self {
+        Self {
+            coupled: true,
+            purge_seconds: 0,
+            prepurge_seconds: 0,
+        }
+    }
+}
+
+/// phase of fan circulation, see `Hvac::with_circulate`
+#[cfg(feature = "fan")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CirculatePhase {
+    /// fan is circulating, with the seconds left in the on phase
+    On(u32),
+    /// fan is resting, with the seconds until it next circulates
+    Off(u32),
+}
+
+/// outstanding request to run the fan, returned by `Hvac::request_fan` and handed back to
+/// `Hvac::release_fan`
+///
+/// deliberately neither `Copy` nor `Clone`, so each request is released exactly once.
+#[cfg(feature = "fan")]
+#[allow(missing_copy_implementations)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[must_use = "the fan runs until the token is released"]
+pub struct FanToken(());
+
+/// change in outputs passed to a hook registered with `Hvac::on_transition`
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HvacTransition {
+    /// state before the change
+    pub from: HvacState,
+    /// state after the change
+    pub to: HvacState,
+    /// seconds elapsed as of the change
+    pub at_seconds: u32,
+}
+
+// function pointer addresses are not guaranteed unique, so hooks never take part in comparing or
+// hashing the state machine and all compare equal
+#[derive(Clone, Copy, Debug)]
+struct TransitionHook(fn(HvacTransition));
+
+impl PartialEq for TransitionHook {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for TransitionHook {}
+
+impl PartialOrd for TransitionHook {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TransitionHook {
+    fn cmp(&self, _other: &Self) -> core::cmp::Ordering {
+        core::cmp::Ordering::Equal
+    }
+}
+
+impl core::hash::Hash for TransitionHook {
+    fn hash<H: core::hash::Hasher>(&self, _state: &mut H) {}
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for TransitionHook {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "TransitionHook")
+    }
+}
+
+/// polarity-adjusted output levels for each terminal
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RawOutputs {
+    /// heat terminal level
+    pub heat: bool,
+    /// cool terminal level
+    #[cfg(feature = "cool")]
+    pub cool: bool,
+    /// fan terminal level
+    #[cfg(feature = "fan")]
     pub fan: bool,
 }
 
+/// full set of time constraints applied at once by `Hvac::reconfigure` and reported by
+/// `Hvac::config`
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HvacConfig {
+    /// heat minimum run time
+    pub heat_min_run_seconds: Option<u32>,
+    /// heat minimum recover time
+    pub heat_min_recover_seconds: Option<u32>,
+    /// cool minimum run time
+    #[cfg(feature = "cool")]
+    pub cool_min_run_seconds: Option<u32>,
+    /// cool minimum recover time
+    #[cfg(feature = "cool")]
+    pub cool_min_recover_seconds: Option<u32>,
+    /// fan minimum run time
+    #[cfg(feature = "fan")]
+    pub fan_min_run_seconds: Option<u32>,
+    /// fan minimum recover time, after both auto and manual stops
+    #[cfg(feature = "fan")]
+    pub fan_min_recover_seconds: Option<u32>,
+    /// fan auto (on with service) or manual (on always)
+    #[cfg(feature = "fan")]
+    pub fan_auto: bool,
+}
+
+impl Default for HvacConfig {
+    fn default() -> Self {
+        Self {
+            heat_min_run_seconds: Some(60),
+            heat_min_recover_seconds: Some(60),
+            #[cfg(feature = "cool")]
+            cool_min_run_seconds: Some(300),
+            #[cfg(feature = "cool")]
+            cool_min_recover_seconds: Some(300),
+            #[cfg(feature = "fan")]
+            fan_min_run_seconds: Some(60),
+            #[cfg(feature = "fan")]
+            fan_min_recover_seconds: Some(60),
+            #[cfg(feature = "fan")]
+            fan_auto: true,
+        }
+    }
+}
+
+/// coarse operating mode of the hvac outputs, ignoring all timers
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OperatingMode {
+    /// no service and fan off
+    Idle,
+    /// heat service active
+    Heating,
+    /// cool service active
+    #[cfg(feature = "cool")]
+    Cooling,
+    /// fan on with no service active
+    #[cfg(feature = "fan")]
+    FanOnly,
+}
+
+const OUTPUT_HEAT_BIT: u8 = 1 << 0;
+#[cfg(feature = "cool")]
+const OUTPUT_COOL_BIT: u8 = 1 << 1;
+#[cfg(feature = "fan")]
+const OUTPUT_FAN_BIT: u8 = 1 << 2;
+#[cfg(feature = "erv")]
+const OUTPUT_ERV_BIT: u8 = 1 << 3;
+
+const STATE_SERVICE_MASK: u8 = 0b11;
+const STATE_HEAT_BITS: u8 = 0b01;
+#[cfg(feature = "cool")]
+const STATE_COOL_BITS: u8 = 0b10;
+#[cfg(feature = "fan")]
+const STATE_FAN_BIT: u8 = 1 << 2;
+#[cfg(all(feature = "fan", feature = "cool"))]
+const STATE_ECONOMIZING_BIT: u8 = 1 << 3;
+#[cfg(feature = "erv")]
+const STATE_ERV_BIT: u8 = 1 << 4;
+const STATE_POWERED_OFF_BIT: u8 = 1 << 6;
+#[cfg(all(feature = "fan", feature = "cool"))]
+const STATE_FREEZE_PROTECTING_BIT: u8 = 1 << 5;
+const STATE_VALID_BITS: u8 = {
+    let bits = STATE_SERVICE_MASK | STATE_POWERED_OFF_BIT;
+    #[cfg(feature = "fan")]
+    let bits = bits | STATE_FAN_BIT;
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    let bits = bits | STATE_ECONOMIZING_BIT | STATE_FREEZE_PROTECTING_BIT;
+    #[cfg(feature = "erv")]
+    let bits = bits | STATE_ERV_BIT;
+    bits
+};
+
+impl HvacState {
+    /// pack the state into a single byte for a stable wire format
+    ///
+    /// bits 0-1 hold the service (0 none, 1 heat, 2 cool), bit 2 the fan, bit 3 economizing,
+    /// bit 4 the energy recovery ventilator, bit 5 freeze protecting, and bit 6 powered off. the
+    /// remaining bit is reserved as zero, as are the bits of any output or mode compiled out by a
+    /// disabled feature, e.g. the cool service without `cool`. only outputs and modes are packed,
+    /// so the advisory short cycling, capacity warning, undersampled, starting up, and valve
+    /// settling flags are not, and are unpacked as `false`.
+    pub fn to_bits(&self) -> u8 {
+        let mut bits = match self.service {
+            None => 0,
+            Some(HvacService::Heat) => STATE_HEAT_BITS,
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => STATE_COOL_BITS,
+        };
+        #[cfg(feature = "fan")]
+        if self.fan {
+            bits |= STATE_FAN_BIT;
+        };
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        {
+            if self.economizing {
+                bits |= STATE_ECONOMIZING_BIT;
+            };
+            if self.freeze_protecting {
+                bits |= STATE_FREEZE_PROTECTING_BIT;
+            };
+        }
+        #[cfg(feature = "erv")]
+        if self.erv {
+            bits |= STATE_ERV_BIT;
+        };
+        if !self.powered {
+            bits |= STATE_POWERED_OFF_BIT;
+        };
+        bits
+    }
+
+    /// unpack a state packed by `to_bits`, or `None` if the service is invalid or any reserved
+    /// bit is set
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        if bits & !STATE_VALID_BITS != 0 {
+            return None;
+        };
+        let service = match bits & STATE_SERVICE_MASK {
+            0 => None,
+            STATE_HEAT_BITS => Some(HvacService::Heat),
+            #[cfg(feature = "cool")]
+            STATE_COOL_BITS => Some(HvacService::Cool),
+            _ => return None,
+        };
+        Some(Self {
+            service,
+            #[cfg(feature = "fan")]
+            fan: bits & STATE_FAN_BIT != 0,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            economizing: bits & STATE_ECONOMIZING_BIT != 0,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            freeze_protecting: bits & STATE_FREEZE_PROTECTING_BIT != 0,
+            #[cfg(feature = "erv")]
+            erv: bits & STATE_ERV_BIT != 0,
+            powered: bits & STATE_POWERED_OFF_BIT == 0,
+            #[cfg(feature = "stats")]
+            short_cycling: false,
+            #[cfg(feature = "stats")]
+            capacity_warning: false,
+            undersampled: false,
+            starting_up: false,
+            #[cfg(feature = "cool")]
+            valve_settling: false,
+        })
+    }
+
+    /// if no service is active and the fan and energy recovery ventilator are off
+    pub fn is_idle(&self) -> bool {
+        #[cfg(feature = "fan")]
+        let fan = self.fan;
+        #[cfg(not(feature = "fan"))]
+        let fan = false;
+        #[cfg(feature = "erv")]
+        let erv = self.erv;
+        #[cfg(not(feature = "erv"))]
+        let erv = false;
+        self.service.is_none() && !fan && !erv
+    }
+
+    /// if any output is on
+    pub fn is_active(&self) -> bool {
+        !self.is_idle()
+    }
+
+    /// collapse the state into its operating mode
+    pub fn operating_mode(&self) -> OperatingMode {
+        match self.service {
+            Some(HvacService::Heat) => OperatingMode::Heating,
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => OperatingMode::Cooling,
+            #[cfg(feature = "fan")]
+            None if self.fan => OperatingMode::FanOnly,
+            None => OperatingMode::Idle,
+        }
+    }
+
+    /// one bit per physical output, for writing directly to a relay bank or gpio expander
+    ///
+    /// bit 0 is heat, bit 1 cool, bit 2 the fan, and bit 3 the energy recovery ventilator, with
+    /// the remaining bits zero. this layout is stable, with any new output taking the next free
+    /// bit. the bit of an output compiled out by a disabled feature is zero. levels are logical,
+    /// before any output polarity, see `Hvac::raw_outputs`.
+    pub fn output_mask(&self) -> u8 {
+        #[cfg_attr(not(any(feature = "fan", feature = "erv")), allow(unused_mut))]
+        let mut mask = match self.service {
+            None => 0,
+            Some(HvacService::Heat) => OUTPUT_HEAT_BIT,
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => OUTPUT_COOL_BIT,
+        };
+        #[cfg(feature = "fan")]
+        if self.fan {
+            mask |= OUTPUT_FAN_BIT;
+        };
+        #[cfg(feature = "erv")]
+        if self.erv {
+            mask |= OUTPUT_ERV_BIT;
+        };
+        mask
+    }
+
+    /// which outputs differ from `previous`, e.g. to switch only the relays that changed
+    pub fn diff(&self, previous: &HvacState) -> StateDiff {
+        StateDiff {
+            service_changed: self.service != previous.service,
+            #[cfg(feature = "fan")]
+            fan_changed: self.fan != previous.fan,
+            #[cfg(feature = "erv")]
+            erv_changed: self.erv != previous.erv,
+        }
+    }
+}
+
+/// outputs that differ between two states, see `HvacState::diff`
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateDiff {
+    /// if the active service changed, including directly between heat and cool
+    pub service_changed: bool,
+    /// if the fan started or stopped
+    #[cfg(feature = "fan")]
+    pub fan_changed: bool,
+    /// if the energy recovery ventilator started or stopped
+    #[cfg(feature = "erv")]
+    pub erv_changed: bool,
+}
+
+impl StateDiff {
+    /// if any output changed
+    pub fn any(&self) -> bool {
+        #[cfg(feature = "fan")]
+        let fan_changed = self.fan_changed;
+        #[cfg(not(feature = "fan"))]
+        let fan_changed = false;
+        #[cfg(feature = "erv")]
+        let erv_changed = self.erv_changed;
+        #[cfg(not(feature = "erv"))]
+        let erv_changed = false;
+        self.service_changed || fan_changed || erv_changed
+    }
+}
+
+/// seconds of runtime accrued over a single tick's interval, see `Hvac::tick_with_runtime`
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RuntimeDelta {
+    /// seconds heat was active
+    pub heat_seconds: u32,
+    /// seconds cool was active
+    #[cfg(feature = "cool")]
+    pub cool_seconds: u32,
+    /// seconds the fan was active
+    #[cfg(feature = "fan")]
+    pub fan_seconds: u32,
+}
+
 /// hvac state machine
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Hvac {
+    // `checksum` hashes the persisted fields in declaration order, so new fields are added at the
+    // end, before the hook
     active_service: Option<HvacService>,
+    #[cfg(feature = "fan")]
     fan_active: bool,
     last_update: Option<u32>,
+    first_update: Option<u32>,
     heat_calling: bool,
     heat_min_run_seconds: Option<u32>,
     heat_min_recover_seconds: Option<u32>,
     heat_wait_seconds: Option<u32>,
     heat_last_start_seconds: Option<u32>,
     heat_last_stop_seconds: Option<u32>,
+    heat_block_reason: Option<BlockReason>,
+    heat_recover_ratio_percent: u16,
+    #[cfg(feature = "cool")]
     cool_calling: bool,
+    #[cfg(feature = "cool")]
     cool_min_run_seconds: Option<u32>,
+    #[cfg(feature = "cool")]
     cool_min_recover_seconds: Option<u32>,
+    #[cfg(feature = "cool")]
+    cool_post_run_off_seconds: Option<u32>,
+    #[cfg(feature = "cool")]
+    cool_last_run_completed: bool,
+    #[cfg(feature = "cool")]
     cool_wait_seconds: Option<u32>,
+    #[cfg(feature = "cool")]
     cool_last_start_seconds: Option<u32>,
+    #[cfg(feature = "cool")]
     cool_last_stop_seconds: Option<u32>,
+    #[cfg(feature = "cool")]
+    cool_block_reason: Option<BlockReason>,
+    #[cfg(feature = "cool")]
+    cool_recover_ratio_percent: u16,
+    #[cfg(feature = "cool")]
+    cool_humidity_lockout_rh: Option<u8>,
+    #[cfg(feature = "cool")]
+    humidity_rh: Option<u8>,
+    #[cfg(feature = "fan")]
     fan_auto: bool,
+    #[cfg(feature = "fan")]
     fan_min_run_seconds: Option<u32>,
+    #[cfg(feature = "fan")]
     fan_min_recover_seconds: Option<u32>,
+    #[cfg(feature = "fan")]
+    fan_manual_off_recover_seconds: Option<u32>,
+    #[cfg(feature = "fan")]
+    fan_manual_released: bool,
+    #[cfg(feature = "fan")]
+    fan_requests: u16,
+    #[cfg(feature = "fan")]
+    circulate: Option<(u32, u32)>,
+    #[cfg(feature = "fan")]
+    fan_last_stop_manual: bool,
+    #[cfg(feature = "fan")]
     fan_wait_seconds: Option<u32>,
+    #[cfg(feature = "fan")]
     fan_last_start_seconds: Option<u32>,
+    #[cfg(feature = "fan")]
     fan_last_stop_seconds: Option<u32>,
+    #[cfg(feature = "erv")]
+    erv_requested: bool,
+    #[cfg(feature = "erv")]
+    erv_active: bool,
+    #[cfg(feature = "erv")]
+    erv_min_run_seconds: Option<u32>,
+    #[cfg(feature = "erv")]
+    erv_min_recover_seconds: Option<u32>,
+    #[cfg(feature = "erv")]
+    erv_wait_seconds: Option<u32>,
+    #[cfg(feature = "erv")]
+    erv_last_start_seconds: Option<u32>,
+    #[cfg(feature = "erv")]
+    erv_last_stop_seconds: Option<u32>,
+    #[cfg(feature = "setpoint")]
+    temperature_history: TemperatureHistory,
+    #[cfg(feature = "setpoint")]
+    scheduled_setpoint: Option<ScheduledSetpoint>,
+    #[cfg(feature = "setpoint")]
+    recovery_service: Option<HvacService>,
+    #[cfg(feature = "setpoint")]
+    occupied: bool,
+    #[cfg(feature = "setpoint")]
+    unoccupied_deadband: i16,
+    #[cfg(feature = "setpoint")]
+    temp_filter_alpha_percent: u8,
+    #[cfg(feature = "setpoint")]
+    filtered_temperature: Option<i16>,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    economize: bool,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    economizer_yield_seconds: Option<u32>,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    economizer_cool_since: Option<u32>,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    economizing: bool,
+    #[cfg(feature = "fan")]
+    start_stagger_seconds: u32,
+    #[cfg(feature = "fan")]
+    start_stagger_until: Option<u32>,
+    #[cfg(feature = "cool")]
+    auto_hold_seconds: u32,
+    min_run_grace_seconds: u32,
+    #[cfg(feature = "cool")]
+    heat_to_cool_delay_seconds: Option<u32>,
+    #[cfg(feature = "cool")]
+    cool_to_heat_delay_seconds: Option<u32>,
+    last_service: Option<HvacService>,
+    #[cfg(feature = "cool")]
+    valve_service: Option<HvacService>,
+    #[cfg(feature = "cool")]
+    valve_settle_seconds: u32,
+    #[cfg(feature = "cool")]
+    valve_changed_seconds: Option<u32>,
+    #[cfg(feature = "cool")]
+    last_changeover_seconds: Option<u32>,
+    #[cfg(feature = "fan")]
+    heat_fan_config: ServiceFanConfig,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    cool_fan_config: ServiceFanConfig,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    fan_continuous_on_changeover: bool,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    cool_coil_overrun: Option<(i16, u32)>,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    coil_temp: Option<i16>,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    freeze_protection: Option<(i16, i16)>,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    freeze_protecting: bool,
+    emergency_stopped: bool,
+    powered: bool,
+    #[cfg(feature = "stats")]
+    short_cycle_warning: Option<(u8, u32)>,
+    heat_min_run_override: Option<u32>,
+    #[cfg(feature = "cool")]
+    cool_min_run_override: Option<u32>,
+    run_min_run_override: Option<u32>,
+    #[cfg(feature = "stats")]
+    short_cycling: bool,
+    #[cfg(feature = "stats")]
+    capacity_warning_seconds: Option<u32>,
+    #[cfg(feature = "cool")]
+    shared_compressor_recover_seconds: Option<u32>,
+    tick_interval_seconds: Option<u32>,
+    tick_late: bool,
+    startup_delay_seconds: u32,
+    call_debounce_seconds: u32,
+    heat_min_call_seconds: u32,
+    #[cfg(feature = "cool")]
+    cool_min_call_seconds: u32,
+    heat_called_since: Option<u32>,
+    #[cfg(feature = "cool")]
+    cool_called_since: Option<u32>,
+    strict: bool,
+    #[cfg(feature = "stats")]
+    capacity_warning: bool,
+    #[cfg(feature = "fan")]
+    emergency_fan_overrun_seconds: u32,
+    #[cfg(feature = "fan")]
+    emergency_purge_until: Option<u32>,
+    held_service: Option<HvacService>,
+    #[cfg(feature = "cool")]
+    conflict_policy: ConflictPolicy,
+    #[cfg(feature = "fan")]
+    fan_ramp_step: u8,
+    #[cfg(feature = "fan")]
+    fan_speed: u8,
+    time_scale_numerator: u32,
+    time_scale_denominator: u32,
+    armed: Option<(HvacService, u32)>,
+    last_tick_advanced: bool,
+    dirty: bool,
+    heat_inverted: bool,
+    #[cfg(feature = "cool")]
+    cool_inverted: bool,
+    #[cfg(feature = "fan")]
+    fan_inverted: bool,
+    #[cfg(feature = "stats")]
+    run_history: RunHistory,
+    #[cfg(feature = "stats")]
+    runtime_stats: RuntimeStats,
+    #[cfg(feature = "stats")]
+    heat_blocked_seconds: u64,
+    #[cfg(all(feature = "stats", feature = "cool"))]
+    cool_blocked_seconds: u64,
+    testing: bool,
+    auto_zero_on_first_call: bool,
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    humidity_fan_modulation: Option<(u8, u8, u8)>,
+    #[cfg(feature = "cool")]
+    heat_only: bool,
+    transition_hook: Option<TransitionHook>,
 }
 
 impl Default for Hvac {
     fn default() -> Self {
         Self {
             active_service: None,
+            #[cfg(feature = "fan")]
             fan_active: false,
             last_update: None,
+            first_update: None,
             heat_calling: false,
             heat_min_run_seconds: Some(60),
             heat_min_recover_seconds: Some(60),
             heat_wait_seconds: Some(60),
             heat_last_start_seconds: None,
             heat_last_stop_seconds: None,
+            heat_block_reason: None,
+            heat_recover_ratio_percent: 0,
+            #[cfg(feature = "cool")]
             cool_calling: false,
+            #[cfg(feature = "cool")]
             cool_min_run_seconds: Some(300),
+            #[cfg(feature = "cool")]
             cool_min_recover_seconds: Some(300),
+            #[cfg(feature = "cool")]
+            cool_post_run_off_seconds: None,
+            #[cfg(feature = "cool")]
+            cool_last_run_completed: false,
+            #[cfg(feature = "cool")]
             cool_wait_seconds: Some(60),
+            #[cfg(feature = "cool")]
             cool_last_start_seconds: None,
+            #[cfg(feature = "cool")]
             cool_last_stop_seconds: None,
+            #[cfg(feature = "cool")]
+            cool_block_reason: None,
+            #[cfg(feature = "cool")]
+            cool_recover_ratio_percent: 0,
+            #[cfg(feature = "cool")]
+            cool_humidity_lockout_rh: None,
+            #[cfg(feature = "cool")]
+            humidity_rh: None,
+            #[cfg(feature = "fan")]
             fan_auto: true,
+            #[cfg(feature = "fan")]
             fan_min_run_seconds: Some(60),
+            #[cfg(feature = "fan")]
             fan_min_recover_seconds: Some(60),
+            #[cfg(feature = "fan")]
+            fan_manual_off_recover_seconds: Some(60),
+            #[cfg(feature = "fan")]
+            fan_manual_released: false,
+            #[cfg(feature = "fan")]
+            fan_requests: 0,
+            #[cfg(feature = "fan")]
+            circulate: None,
+            #[cfg(feature = "fan")]
+            fan_last_stop_manual: false,
+            #[cfg(feature = "fan")]
             fan_wait_seconds: Some(60),
+            #[cfg(feature = "fan")]
             fan_last_start_seconds: None,
+            #[cfg(feature = "fan")]
             fan_last_stop_seconds: None,
+            #[cfg(feature = "erv")]
+            erv_requested: false,
+            #[cfg(feature = "erv")]
+            erv_active: false,
+            #[cfg(feature = "erv")]
+            erv_min_run_seconds: Some(60),
+            #[cfg(feature = "erv")]
+            erv_min_recover_seconds: Some(60),
+            #[cfg(feature = "erv")]
+            erv_wait_seconds: Some(60),
+            #[cfg(feature = "erv")]
+            erv_last_start_seconds: None,
+            #[cfg(feature = "erv")]
+            erv_last_stop_seconds: None,
+            #[cfg(feature = "setpoint")]
+            temperature_history: TemperatureHistory::default(),
+            #[cfg(feature = "setpoint")]
+            scheduled_setpoint: None,
+            #[cfg(feature = "setpoint")]
+            recovery_service: None,
+            #[cfg(feature = "setpoint")]
+            occupied: true,
+            #[cfg(feature = "setpoint")]
+            unoccupied_deadband: 0,
+            #[cfg(feature = "setpoint")]
+            temp_filter_alpha_percent: 100,
+            #[cfg(feature = "setpoint")]
+            filtered_temperature: None,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            economize: false,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            economizer_yield_seconds: Some(300),
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            economizer_cool_since: None,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            economizing: false,
+            #[cfg(feature = "fan")]
+            start_stagger_seconds: 0,
+            #[cfg(feature = "fan")]
+            start_stagger_until: None,
+            #[cfg(feature = "cool")]
+            auto_hold_seconds: 0,
+            min_run_grace_seconds: 0,
+            #[cfg(feature = "cool")]
+            heat_to_cool_delay_seconds: None,
+            #[cfg(feature = "cool")]
+            cool_to_heat_delay_seconds: None,
+            last_service: None,
+            #[cfg(feature = "cool")]
+            valve_service: None,
+            #[cfg(feature = "cool")]
+            valve_settle_seconds: 0,
+            #[cfg(feature = "cool")]
+            valve_changed_seconds: None,
+            #[cfg(feature = "cool")]
+            last_changeover_seconds: None,
+            #[cfg(feature = "fan")]
+            heat_fan_config: ServiceFanConfig::default(),
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            cool_fan_config: ServiceFanConfig::default(),
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            fan_continuous_on_changeover: false,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            cool_coil_overrun: None,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            coil_temp: None,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            freeze_protection: None,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            freeze_protecting: false,
+            emergency_stopped: false,
+            powered: true,
+            #[cfg(feature = "stats")]
+            short_cycle_warning: None,
+            heat_min_run_override: None,
+            #[cfg(feature = "cool")]
+            cool_min_run_override: None,
+            run_min_run_override: None,
+            #[cfg(feature = "stats")]
+            short_cycling: false,
+            #[cfg(feature = "stats")]
+            capacity_warning_seconds: None,
+            #[cfg(feature = "cool")]
+            shared_compressor_recover_seconds: None,
+            tick_interval_seconds: None,
+            tick_late: false,
+            startup_delay_seconds: 0,
+            call_debounce_seconds: 0,
+            heat_min_call_seconds: 0,
+            #[cfg(feature = "cool")]
+            cool_min_call_seconds: 0,
+            heat_called_since: None,
+            #[cfg(feature = "cool")]
+            cool_called_since: None,
+            strict: false,
+            #[cfg(feature = "stats")]
+            capacity_warning: false,
+            #[cfg(feature = "fan")]
+            emergency_fan_overrun_seconds: 0,
+            #[cfg(feature = "fan")]
+            emergency_purge_until: None,
+            held_service: None,
+            #[cfg(feature = "cool")]
+            conflict_policy: ConflictPolicy::LastWins,
+            #[cfg(feature = "fan")]
+            fan_ramp_step: 0,
+            #[cfg(feature = "fan")]
+            fan_speed: 0,
+            time_scale_numerator: 1,
+            time_scale_denominator: 1,
+            armed: None,
+            last_tick_advanced: false,
+            dirty: true,
+            heat_inverted: false,
+            #[cfg(feature = "cool")]
+            cool_inverted: false,
+            #[cfg(feature = "fan")]
+            fan_inverted: false,
+            #[cfg(feature = "stats")]
+            run_history: RunHistory::default(),
+            #[cfg(feature = "stats")]
+            runtime_stats: RuntimeStats::default(),
+            #[cfg(feature = "stats")]
+            heat_blocked_seconds: 0,
+            #[cfg(all(feature = "stats", feature = "cool"))]
+            cool_blocked_seconds: 0,
+            testing: false,
+            auto_zero_on_first_call: false,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            humidity_fan_modulation: None,
+            #[cfg(feature = "cool")]
+            heat_only: false,
+            transition_hook: None,
         }
     }
 }
 
+/// upper bound applied to each constraint by the clamped builders such as `Hvac::with_heat_clamped`
+pub const MAX_CLAMPED_CONSTRAINT_SECONDS: u32 = 3600;
+
+fn clamp_constraint(seconds: Option<u32>) -> Option<u32> {
+    seconds.map(|seconds| seconds.min(MAX_CLAMPED_CONSTRAINT_SECONDS))
+}
+
+/// a tick is late once it arrives more than this fraction of the expected interval after it
+const TICK_LATE_MARGIN_DIVISOR: u32 = 2;
+
+/// upper bound on a recover time derived from run length, so a very long run cannot lock a
+/// service out indefinitely
+const MAX_PROPORTIONAL_RECOVER_SECONDS: u32 = 3600;
+
+fn proportional_recover_seconds(
+    ratio_percent: u16,
+    last_start: Option<u32>,
+    last_stop: Option<u32>,
+) -> Option<u32> {
+    if ratio_percent == 0 {
+        return None;
+    };
+    let run_seconds = last_stop?.saturating_sub(last_start?);
+    let recover_seconds = u64::from(run_seconds) * u64::from(ratio_percent) / 100;
+    Some(recover_seconds.min(u64::from(MAX_PROPORTIONAL_RECOVER_SECONDS)) as u32)
+}
+
+#[cfg(feature = "stats")]
+fn is_wait_timer(reason: BlockReason) -> bool {
+    match reason {
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        BlockReason::Economizer => false,
+        #[cfg(feature = "cool")]
+        BlockReason::HumidityLockout | BlockReason::ConflictPolicy => false,
+        BlockReason::EmergencyStop | BlockReason::PoweredOff => false,
+        _ => true,
+    }
+}
+
 fn wait_seconds(
     last_update: Option<u32>,
     min_seconds: Option<u32>,
@@ -212,7 +1296,7 @@ fn wait_seconds(
 ) -> Option<u32> {
     if let Some(last_update) = last_update {
         if let Some(min_seconds) = min_seconds {
-            let delta = last_update - last_change.unwrap_or(0);
+            let delta = last_update.saturating_sub(last_change.unwrap_or(0));
             if delta < min_seconds {
                 Some(min_seconds - delta)
             } else {
@@ -227,6 +1311,70 @@ fn wait_seconds(
 }
 
 impl Hvac {
+    /// resume a controller whose outputs were last observed as `state` at `at_seconds` elapsed
+    ///
+    /// internal timers are unknown after a restart, so they are conservatively assumed to have
+    /// just changed: active outputs are treated as having started at `at_seconds` and inactive
+    /// ones as having stopped at `at_seconds`. this means min run and min recover times restart
+    /// from the resume time. no calls for service are assumed, so any outstanding call must be
+    /// made again, and the energy recovery ventilator must be requested again to keep it on.
+    /// custom constraints may be applied with the usual `with_*` builders.
+    pub fn resuming(state: HvacState, at_seconds: u32) -> Self {
+        let mut hvac = Self {
+            active_service: state.service,
+            last_service: state.service,
+            #[cfg(feature = "cool")]
+            valve_service: state.service,
+            #[cfg(feature = "fan")]
+            fan_active: state.fan,
+            #[cfg(feature = "erv")]
+            erv_active: state.erv,
+            powered: state.powered,
+            last_update: Some(at_seconds),
+            first_update: Some(at_seconds),
+            ..Self::default()
+        };
+        if state.service == Some(HvacService::Heat) {
+            hvac.heat_last_start_seconds = Some(at_seconds);
+        } else {
+            hvac.heat_last_stop_seconds = Some(at_seconds);
+        };
+        #[cfg(feature = "cool")]
+        if state.service == Some(HvacService::Cool) {
+            hvac.cool_last_start_seconds = Some(at_seconds);
+        } else {
+            hvac.cool_last_stop_seconds = Some(at_seconds);
+        };
+        #[cfg(feature = "stats")]
+        if let Some(service) = state.service {
+            hvac.run_history.start(service, at_seconds);
+        };
+        #[cfg(feature = "fan")]
+        if state.fan {
+            hvac.fan_last_start_seconds = Some(at_seconds);
+        } else {
+            hvac.fan_last_stop_seconds = Some(at_seconds);
+        };
+        #[cfg(feature = "erv")]
+        if state.erv {
+            hvac.erv_last_start_seconds = Some(at_seconds);
+        } else {
+            hvac.erv_last_stop_seconds = Some(at_seconds);
+        };
+        hvac
+    }
+
+    /// new state machine with every time constraint and the fan mode taken from `config`, e.g. as
+    /// deserialized from settings, and no timing history
+    ///
+    /// equivalent to `reconfigure` on a default state machine. other settings may still be applied
+    /// with the usual `with_*` builders.
+    pub fn from_config(config: HvacConfig) -> Self {
+        let mut hvac = Self::default();
+        hvac.reconfigure(config);
+        hvac
+    }
+
     /// use custom heat run and recover time constraints
     pub fn with_heat(
         mut self,
@@ -235,10 +1383,12 @@ impl Hvac {
     ) -> Self {
         self.heat_min_run_seconds = min_run_seconds;
         self.heat_min_recover_seconds = min_recover_seconds;
+        self.dirty = true;
         self
     }
 
     /// use custom cool run and recover time constraints
+    #[cfg(feature = "cool")]
     pub fn with_cool(
         mut self,
         min_run_seconds: Option<u32>,
@@ -246,10 +1396,62 @@ impl Hvac {
     ) -> Self {
         self.cool_min_run_seconds = min_run_seconds;
         self.cool_min_recover_seconds = min_recover_seconds;
+        self.dirty = true;
+        self
+    }
+
+    /// configure an install without cooling, e.g. a furnace alone, ignoring any call for cool
+    ///
+    /// explicit, armed, test, and scheduled setpoint recovery calls for cool are all ignored, and
+    /// calling `cool` is treated as misuse in strict mode. without the `cool` feature the
+    /// controller is always heat-only.
+    #[cfg(feature = "cool")]
+    pub fn with_heat_only(mut self, heat_only: bool) -> Self {
+        self.heat_only = heat_only;
+        self.dirty = true;
+        self
+    }
+
+    /// use custom cool off time constraint applied only after a completed cycle
+    ///
+    /// a cycle is completed when cool stops after satisfying its min run time. after a completed
+    /// cycle cool waits for the longer of its min recover time and this post run off time, while
+    /// after an interrupted cycle only its min recover time applies.
+    #[cfg(feature = "cool")]
+    pub fn with_cool_post_run_off(mut self, post_run_off_seconds: Option<u32>) -> Self {
+        self.cool_post_run_off_seconds = post_run_off_seconds;
+        self.dirty = true;
+        self
+    }
+
+    /// lock out calls for cool while the measured relative humidity is below `min_rh_percent`,
+    /// or `None` to never lock out cool on humidity
+    ///
+    /// no lockout applies until a humidity is provided with `set_humidity`.
+    #[cfg(feature = "cool")]
+    pub fn with_cool_humidity_lockout(mut self, min_rh_percent: Option<u8>) -> Self {
+        self.cool_humidity_lockout_rh = min_rh_percent;
+        self.dirty = true;
+        self
+    }
+
+    /// scale the recover time of `service` with the length of the run that just ended, so after
+    /// stopping it waits for the longer of its configured recover time and `ratio_percent` of
+    /// the run length
+    ///
+    /// the derived recover time is capped at one hour, and a ratio of 0 disables it.
+    pub fn with_proportional_recover(mut self, service: HvacService, ratio_percent: u16) -> Self {
+        match service {
+            HvacService::Heat => self.heat_recover_ratio_percent = ratio_percent,
+            #[cfg(feature = "cool")]
+            HvacService::Cool => self.cool_recover_ratio_percent = ratio_percent,
+        };
+        self.dirty = true;
         self
     }
 
     /// use custom fan run and recover time constraints
+    #[cfg(feature = "fan")]
     pub fn with_fan(
         mut self,
         min_run_seconds: Option<u32>,
@@ -257,155 +1459,3132 @@ impl Hvac {
     ) -> Self {
         self.fan_min_run_seconds = min_run_seconds;
         self.fan_min_recover_seconds = min_recover_seconds;
+        self.fan_manual_off_recover_seconds = min_recover_seconds;
+        self.dirty = true;
         self
     }
 
-    fn state(&self) -> HvacState {
-        HvacState {
-            service: self.active_service,
-            fan: self.fan_active,
-        }
+    /// use custom heat run and recover time constraints, clamping each to at most
+    /// `MAX_CLAMPED_CONSTRAINT_SECONDS` (one hour) rather than rejecting them
+    ///
+    /// intended for constraints from untrusted configuration, where a clamped value is preferable
+    /// to handling an error. `None` is kept as no constraint.
+    pub fn with_heat_clamped(
+        self,
+        min_run_seconds: Option<u32>,
+        min_recover_seconds: Option<u32>,
+    ) -> Self {
+        self.with_heat(
+            clamp_constraint(min_run_seconds),
+            clamp_constraint(min_recover_seconds),
+        )
     }
 
-    fn compute(&mut self) -> HvacState {
-        self.heat_wait_seconds = if self.active_service == Some(HvacService::Heat) {
-            wait_seconds(
-                self.last_update,
-                self.heat_min_run_seconds,
-                self.heat_last_start_seconds,
-            )
-        } else {
-            wait_seconds(
-                self.last_update,
-                self.heat_min_recover_seconds,
-                self.heat_last_stop_seconds,
-            )
-        };
-
-        self.cool_wait_seconds = if self.active_service == Some(HvacService::Cool) {
-            wait_seconds(
-                self.last_update,
-                self.cool_min_run_seconds,
-                self.cool_last_start_seconds,
-            )
-        } else {
-            wait_seconds(
-                self.last_update,
-                self.cool_min_recover_seconds,
-                self.cool_last_stop_seconds,
-            )
-        };
+    /// use custom cool run and recover time constraints, clamped as by `with_heat_clamped`
+    #[cfg(feature = "cool")]
+    pub fn with_cool_clamped(
+        self,
+        min_run_seconds: Option<u32>,
+        min_recover_seconds: Option<u32>,
+    ) -> Self {
+        self.with_cool(
+            clamp_constraint(min_run_seconds),
+            clamp_constraint(min_recover_seconds),
+        )
+    }
 
-        self.fan_wait_seconds = if self.fan_active {
-            wait_seconds(
-                self.last_update,
-                self.fan_min_run_seconds,
-                self.fan_last_start_seconds,
-            )
-        } else {
-            wait_seconds(
-                self.last_update,
-                self.fan_min_recover_seconds,
-                self.fan_last_stop_seconds,
-            )
-        };
+    /// use custom fan run and recover time constraints, clamped as by `with_heat_clamped`
+    #[cfg(feature = "fan")]
+    pub fn with_fan_clamped(
+        self,
+        min_run_seconds: Option<u32>,
+        min_recover_seconds: Option<u32>,
+    ) -> Self {
+        self.with_fan(
+            clamp_constraint(min_run_seconds),
+            clamp_constraint(min_recover_seconds),
+        )
+    }
 
-        if let Some(active_service) = self.active_service {
-            match active_service {
-                HvacService::Heat => {
-                    if !self.heat_calling && self.heat_wait_seconds.is_none() {
-                        self.heat_last_stop_seconds = self.last_update;
-                        self.active_service = None;
-                        if self.cool_calling && self.cool_wait_seconds.is_none() {
-                            self.cool_last_start_seconds = self.last_update;
-                            self.active_service = Some(HvacService::Cool);
-                        } else if self.fan_auto && self.fan_wait_seconds.is_none() {
-                            self.fan_last_stop_seconds = self.last_update;
-                            self.fan_active = false;
-                        };
-                    };
-                }
-                HvacService::Cool => {
-                    if !self.cool_calling && self.cool_wait_seconds.is_none() {
-                        self.cool_last_stop_seconds = self.last_update;
-                        self.active_service = None;
-                        if self.heat_calling && self.heat_wait_seconds.is_none() {
-                            self.heat_last_start_seconds = self.last_update;
-                            self.active_service = Some(HvacService::Heat);
-                        } else if self.fan_auto && self.fan_wait_seconds.is_none() {
-                            self.fan_last_stop_seconds = self.last_update;
-                            self.fan_active = false;
-                        };
-                    };
-                }
-            };
-        } else if self.heat_calling && self.heat_wait_seconds.is_none() {
-            if !self.fan_active && self.fan_wait_seconds.is_none() {
-                self.fan_last_start_seconds = self.last_update;
-                self.fan_active = true;
-            };
-            if self.fan_active {
-                self.heat_last_start_seconds = self.last_update;
-                self.active_service = Some(HvacService::Heat);
-            };
-        } else if self.cool_calling && self.cool_wait_seconds.is_none() {
-            if !self.fan_active && self.fan_wait_seconds.is_none() {
-                self.fan_last_start_seconds = self.last_update;
-                self.fan_active = true;
-            };
-            if self.fan_active {
-                self.cool_last_start_seconds = self.last_update;
-                self.active_service = Some(HvacService::Cool);
-            };
-        };
+    /// use custom fan recover time constraints depending on why the fan last stopped
+    ///
+    /// `auto_off_recover_seconds` applies after the fan stops on its own in auto mode, e.g. when
+    /// a service ends, while `manual_off_recover_seconds` applies after manually running the fan
+    /// is ended by returning it to auto mode
+    #[cfg(feature = "fan")]
+    pub fn with_fan_recover(
+        mut self,
+        auto_off_recover_seconds: Option<u32>,
+        manual_off_recover_seconds: Option<u32>,
+    ) -> Self {
+        self.fan_min_recover_seconds = auto_off_recover_seconds;
+        self.fan_manual_off_recover_seconds = manual_off_recover_seconds;
+        self.dirty = true;
+        self
+    }
 
-        if self.fan_active && self.fan_auto {
-            if self.active_service.is_none() && self.fan_wait_seconds.is_none() {
-                self.fan_active = false;
-            };
-        } else if !self.fan_auto && self.fan_wait_seconds.is_none() {
-            self.fan_active = true;
-        };
+    /// use custom energy recovery ventilator run and recover time constraints
+    #[cfg(feature = "erv")]
+    pub fn with_erv(
+        mut self,
+        min_run_seconds: Option<u32>,
+        min_recover_seconds: Option<u32>,
+    ) -> Self {
+        self.erv_min_run_seconds = min_run_seconds;
+        self.erv_min_recover_seconds = min_recover_seconds;
+        self.dirty = true;
+        self
+    }
 
-        self.state()
+    /// use custom time a call for cool is served by the economizer alone before the compressor
+    /// is allowed to start, or `None` to never start the compressor while economizing
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub fn with_economizer_yield(mut self, yield_seconds: Option<u32>) -> Self {
+        self.economizer_yield_seconds = yield_seconds;
+        self.dirty = true;
+        self
     }
 
-    /// update the state machine with new seconds elappsed value
-    pub fn tick(&mut self, current_seconds: u32) -> HvacState {
-        self.last_update = Some(current_seconds);
-        self.compute()
+    /// defer a service by `seconds` when it would otherwise start in the same tick as the fan,
+    /// staggering their inrush current
+    #[cfg(feature = "fan")]
+    pub fn with_start_stagger(mut self, seconds: u32) -> Self {
+        self.start_stagger_seconds = seconds;
+        self.dirty = true;
+        self
     }
 
-    /// update state machine with a call for heat, disabling call for cool in the process
+    /// hold off another changeover between heat and cool for `seconds` after one occurs; unlike
+    /// the changeover delay, which enforces an off gap before the opposing service starts, this
+    /// caps how often the system may switch between heat and cool
+    #[cfg(feature = "cool")]
+    pub fn with_auto_hold(mut self, seconds: u32) -> Self {
+        self.auto_hold_seconds = seconds;
+        self.dirty = true;
+        self
+    }
+
+    /// allow heat or cool to stop when called off within `seconds` of completing its min run,
+    /// rather than holding it on for the remainder
+    ///
+    /// this smooths calls that end just short of min run, at the cost of an effective min run up
+    /// to `seconds` shorter. a run stopped within the grace counts as completed.
+    pub fn with_min_run_grace(mut self, seconds: u32) -> Self {
+        self.min_run_grace_seconds = seconds;
+        self.dirty = true;
+        self
+    }
+
+    /// use `seconds` as the min recover time of both heat and cool, and share it between them as
+    /// on a heat pump where both run the same compressor
+    ///
+    /// neither service starts until `seconds` after either last stopped, so stopping cool also
+    /// holds off heat and vice versa. this is in addition to each service's own recover time,
+    /// which later builders such as `with_heat` may change.
+    #[cfg(feature = "cool")]
+    pub fn with_shared_compressor_recover(mut self, seconds: u32) -> Self {
+        self.heat_min_recover_seconds = Some(seconds);
+        self.cool_min_recover_seconds = Some(seconds);
+        self.shared_compressor_recover_seconds = Some(seconds);
+        self.dirty = true;
+        self
+    }
+
+    /// use custom off time constraints between one service stopping and the opposing service
+    /// starting, which may differ by the direction of the changeover
+    #[cfg(feature = "cool")]
+    pub fn with_changeover_delay(
+        mut self,
+        heat_to_cool_seconds: Option<u32>,
+        cool_to_heat_seconds: Option<u32>,
+    ) -> Self {
+        self.heat_to_cool_delay_seconds = heat_to_cool_seconds;
+        self.cool_to_heat_delay_seconds = cool_to_heat_seconds;
+        self.dirty = true;
+        self
+    }
+
+    /// widen the deadband around a scheduled setpoint by `extra` while unoccupied, so recovery
+    /// only begins once the temperature strays further from it, see `set_occupancy`
+    #[cfg(feature = "setpoint")]
+    pub fn with_unoccupied_deadband(mut self, extra: i16) -> Self {
+        self.unoccupied_deadband = extra;
+        self.dirty = true;
+        self
+    }
+
+    /// smooth observed temperatures with an exponential moving average, moving the smoothed value
+    /// `alpha_percent` of the way toward each new observation
+    ///
+    /// 100 (the default) disables smoothing, while smaller values smooth more heavily, e.g. 20
+    /// weights each observation at a fifth. values above 100 are treated as 100 and 0 as 1. the
+    /// first observation is taken as is, and integer rounding means the smoothed value may settle
+    /// up to `50 / alpha_percent` units short of a steady observation.
+    #[cfg(feature = "setpoint")]
+    pub fn with_temp_filter(mut self, alpha_percent: u8) -> Self {
+        self.temp_filter_alpha_percent = alpha_percent.clamp(1, 100);
+        self.dirty = true;
+        self
+    }
+
+    /// use custom setting for whether the fan runs with heat, disabling it for systems such as
+    /// hydronic heat that do not use the air handler
+    #[cfg(feature = "fan")]
+    pub fn with_fan_on_heat(self, fan_on_heat: bool) -> Self {
+        self.with_heat_fan_coupled(fan_on_heat)
+    }
+
+    /// use custom setting for whether the fan starts and stops with heat, as `with_fan_on_heat`
+    ///
+    /// a service that is not coupled neither starts the fan nor waits on its min recover time, so
+    /// on a changeover to a coupled service the fan starts with it, subject to its usual timers.
+    #[cfg(feature = "fan")]
+    pub fn with_heat_fan_coupled(mut self, coupled: bool) -> Self {
+        self.heat_fan_config.coupled = coupled;
+        self.dirty = true;
+        self
+    }
+
+    /// use custom setting for whether the fan starts and stops with cool, see
+    /// `with_heat_fan_coupled`
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub fn with_cool_fan_coupled(mut self, coupled: bool) -> Self {
+        self.cool_fan_config.coupled = coupled;
+        self.dirty = true;
+        self
+    }
+
+    /// use custom fan coupling, purge and pre-purge for heat
+    ///
+    /// a purge keeps a fan already running on after heat stops and a pre-purge defers heat after
+    /// starting the fan with it, each alongside the fan's own timers. a pre-purge shorter than
+    /// the start stagger has no effect, and neither applies while emergency stopped.
+    #[cfg(feature = "fan")]
+    pub fn with_heat_fan_config(mut self, config: ServiceFanConfig) -> Self {
+        self.heat_fan_config = config;
+        self.dirty = true;
+        self
+    }
+
+    /// use custom fan coupling, purge and pre-purge for cool, see `with_heat_fan_config`
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub fn with_cool_fan_config(mut self, config: ServiceFanConfig) -> Self {
+        self.cool_fan_config = config;
+        self.dirty = true;
+        self
+    }
+
+    /// use custom setting for whether the fan stays on continuously through a changeover between
+    /// heat and cool, rather than stopping in auto once its min run is met
+    ///
+    /// while enabled, a running fan is held on from the moment one service stops until the
+    /// other, already called for, starts, so the handoff neither drops the fan nor restarts its
+    /// min run and recover times.
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub fn with_fan_continuous_on_changeover(mut self, continuous: bool) -> Self {
+        self.fan_continuous_on_changeover = continuous;
+        self.dirty = true;
+        self
+    }
+
+    /// pause the compressor while cool is active and the coil temperature is below
+    /// `pause_below`, resuming it once the coil warms above `resume_above`
+    ///
+    /// the pause is not a stop: the call for cool remains, the fan keeps running to de-ice the
+    /// coil, no recover time applies on resuming, and min run continues to elapse. coil
+    /// temperature is provided with `set_coil_temp`.
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub fn with_freeze_protection(mut self, pause_below: i16, resume_above: i16) -> Self {
+        self.freeze_protection = Some((pause_below, resume_above));
+        self.dirty = true;
+        self
+    }
+
+    /// keep the fan running for `seconds` after an emergency stop that asks for a purge, e.g. to
+    /// clear combustion byproducts from the heat exchanger, see `emergency_stop`
+    #[cfg(feature = "fan")]
+    pub fn with_emergency_fan_overrun(mut self, seconds: u32) -> Self {
+        self.emergency_fan_overrun_seconds = seconds;
+        self.dirty = true;
+        self
+    }
+
+    /// keep the fan running in auto mode after cool stops, extracting the cooling stored in the
+    /// coil, until the coil temperature rises above `stop_above` or `max_seconds` pass
+    ///
+    /// coil temperature is provided with `set_coil_temp`, and without one the fan runs for the
+    /// full `max_seconds`.
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub fn with_cool_coil_overrun(mut self, stop_above: i16, max_seconds: u32) -> Self {
+        self.cool_coil_overrun = Some((stop_above, max_seconds));
+        self.dirty = true;
+        self
+    }
+
+    /// use custom output polarity, inverting the raw level of each terminal as specified
+    pub fn with_output_polarity(
+        mut self,
+        heat_inverted: bool,
+        #[cfg(feature = "cool")] cool_inverted: bool,
+        #[cfg(feature = "fan")] fan_inverted: bool,
+    ) -> Self {
+        self.heat_inverted = heat_inverted;
+        #[cfg(feature = "cool")]
+        {
+            self.cool_inverted = cool_inverted;
+        }
+        #[cfg(feature = "fan")]
+        {
+            self.fan_inverted = fan_inverted;
+        }
+        self.dirty = true;
+        self
+    }
+
+    /// raise `HvacState::short_cycling` while heat or cool has started more than `max_starts`
+    /// times within the last `window_seconds`, e.g. to flag a stuck sensor
+    ///
+    /// this is advisory only and never holds off a service. the flag clears once enough starts
+    /// fall outside the window. starts are counted from the run history, which holds the last 16
+    /// runs, so `max_starts` of 16 or more never raises it.
+    #[cfg(feature = "stats")]
+    pub fn with_short_cycle_warning(mut self, max_starts: u8, window_seconds: u32) -> Self {
+        self.short_cycle_warning = Some((max_starts, window_seconds));
+        self.dirty = true;
+        self
+    }
+
+    /// raise `HvacState::capacity_warning` while heat or cool has run continuously for at least
+    /// `seconds` and is still called for, e.g. to flag undersized equipment or a leak
+    ///
+    /// this is advisory only and clears once the call ends.
+    #[cfg(feature = "stats")]
+    pub fn with_capacity_warning(mut self, seconds: u32) -> Self {
+        self.capacity_warning_seconds = Some(seconds);
+        self.dirty = true;
+        self
+    }
+
+    /// raise `HvacState::undersampled` when ticks are expected every `seconds` but arrive more
+    /// than one and a half times that apart, or any configured min run or recover time is
+    /// shorter than `seconds`, as either keeps constraints from being honored precisely
+    ///
+    /// the late tick check reflects the most recent tick that moved time forward.
+    pub fn with_tick_interval(mut self, seconds: u32) -> Self {
+        self.tick_interval_seconds = Some(seconds);
+        self.dirty = true;
+        self
+    }
+
+    /// hold heat and cool off for `seconds` after the first tick regardless of calls, e.g. to let
+    /// sensors settle and avoid a surge on power up
+    ///
+    /// the delay runs alongside each service's min recover time rather than adding to it, so a
+    /// call waits for whichever ends later. a state machine resumed with `resuming` keeps
+    /// any service already active and only holds off new starts.
+    pub fn with_startup_delay(mut self, seconds: u32) -> Self {
+        self.startup_delay_seconds = seconds;
+        self.dirty = true;
+        self
+    }
+
+    /// defer acting on a new call for heat or cool until it has persisted for `seconds`, e.g. to
+    /// ignore a call that flickers on and off with sensor noise
+    ///
+    /// the debounce runs alongside the service's min recover time rather than adding to it, so a
+    /// call waits for whichever ends later. as nothing starts during the debounce, a call retracted
+    /// within it arms no recover time and counts no cycle. the call of an active service is not
+    /// debounced.
+    pub fn with_call_debounce(mut self, seconds: u32) -> Self {
+        self.call_debounce_seconds = seconds;
+        self.dirty = true;
+        self
+    }
+
+    /// hold off starting `service` until its call has been continuously held for `seconds`, e.g.
+    /// so that brief calls for cool do not cycle the compressor
+    ///
+    /// a call is otherwise acted on as soon as it is made. this is timed and reported exactly as
+    /// the call debounce, whichever is longer applying, so it too runs alongside the min recover
+    /// time and arms nothing for a call that ends early.
+    pub fn with_min_call_duration(mut self, service: HvacService, seconds: u32) -> Self {
+        match service {
+            HvacService::Heat => self.heat_min_call_seconds = seconds,
+            #[cfg(feature = "cool")]
+            HvacService::Cool => self.cool_min_call_seconds = seconds,
+        };
+        self.dirty = true;
+        self
+    }
+
+    /// on a changeover between heat and cool, move the reversing valve to the new service and
+    /// hold the compressor off for `seconds` while it settles
+    ///
+    /// this sequences the valve ahead of the compressor within a start, unlike the changeover
+    /// delay, which is the off time between the two services. the valve stays in its new position
+    /// even if the call ends while it settles.
+    #[cfg(feature = "cool")]
+    pub fn with_valve_settle(mut self, seconds: u32) -> Self {
+        self.valve_settle_seconds = seconds;
+        self.dirty = true;
+        self
+    }
+
+    /// use custom policy for resolving simultaneous calls for heat and cool
+    ///
+    /// with any policy other than `LastWins` a call for one service leaves a call for the other in
+    /// place, so both remain present (and resolved by the policy) until `idle` clears them
+    #[cfg(feature = "cool")]
+    pub fn with_conflict_policy(mut self, conflict_policy: ConflictPolicy) -> Self {
+        self.conflict_policy = conflict_policy;
+        self.dirty = true;
+        self
+    }
+
+    /// circulate air with the fan in auto, running it for `on_seconds` of every
+    /// `on_seconds + off_seconds` while no service is active
+    ///
+    /// phases are aligned to seconds elapsed rather than to any service, and the fan keeps its
+    /// usual min run and recover times, which may trim a phase short of its configured length.
+    #[cfg(feature = "fan")]
+    pub fn with_circulate(mut self, on_seconds: u32, off_seconds: u32) -> Self {
+        self.circulate = Some((on_seconds, off_seconds));
+        self.dirty = true;
+        self
+    }
+
+    /// ramp the reported fan speed toward its target by at most `step_per_tick` each tick, or
+    /// move it immediately with a step of 0
+    #[cfg(feature = "fan")]
+    pub fn with_fan_ramp(mut self, step_per_tick: u8) -> Self {
+        self.fan_ramp_step = step_per_tick;
+        self.dirty = true;
+        self
+    }
+
+    /// while cool is active, target a fan speed of `high_rh_speed` once the relative humidity
+    /// reaches `threshold_rh_percent` and `low_rh_speed` below it, e.g. slower to remove more
+    /// moisture when it is humid
+    ///
+    /// the fan targets full speed otherwise, including before any humidity is measured.
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub fn with_humidity_fan_modulation(
+        mut self,
+        high_rh_speed: u8,
+        low_rh_speed: u8,
+        threshold_rh_percent: u8,
+    ) -> Self {
+        self.humidity_fan_modulation = Some((high_rh_speed, low_rh_speed, threshold_rh_percent));
+        self.dirty = true;
+        self
+    }
+
+    /// scale the seconds elapsed passed to `tick` and `schedule_setpoint` by
+    /// `numerator / denominator`, e.g. 60 / 1 to run 60 times faster for testing
+    ///
+    /// all constraints remain in scaled seconds. the scaled time is truncated to whole seconds,
+    /// so non-integer ratios may lag by up to a second, though the error does not accumulate
+    /// across ticks. a denominator of 0 disables scaling.
+    pub fn with_time_scale(mut self, numerator: u32, denominator: u32) -> Self {
+        self.time_scale_numerator = numerator;
+        self.time_scale_denominator = denominator;
+        self.dirty = true;
+        self
+    }
+
+    /// panic on misuse of the api in builds with debug assertions, e.g. to surface bugs in tests
+    ///
+    /// currently a `tick` earlier than the previous one, and `cool` on a controller configured
+    /// with `with_heat_only`, are treated as misuse. without debug assertions, or with strict mode
+    /// off (the default), such misuse is tolerated as documented on each method.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// treat a call or command made before the first tick as made at time zero, as though
+    /// `tick(0)` had been called first
+    ///
+    /// the state machine then counts as ticked, with its uptime and startup delay measured from
+    /// zero. a later `tick` that jumps to a large value is treated as that much time having
+    /// elapsed since zero, so any service started by the call accrues runtime over the jump and
+    /// every timer measured from zero is satisfied accordingly.
+    pub fn with_auto_zero_on_first_call(mut self, auto_zero: bool) -> Self {
+        self.auto_zero_on_first_call = auto_zero;
+        self
+    }
+
+    /// check that the configuration is internally consistent
+    pub fn validate(&self) -> Result<(), HvacConfigError> {
+        let never_elapses = |min_run_seconds: Option<u32>, min_recover_seconds: Option<u32>| {
+            min_run_seconds == Some(u32::MAX) || min_recover_seconds == Some(u32::MAX)
+        };
+        if never_elapses(self.heat_min_run_seconds, self.heat_min_recover_seconds) {
+            return Err(HvacConfigError::HeatConstraintNeverElapses);
+        };
+        #[cfg(feature = "cool")]
+        if never_elapses(self.cool_min_run_seconds, self.cool_min_recover_seconds)
+            || never_elapses(None, self.cool_post_run_off_seconds)
+        {
+            return Err(HvacConfigError::CoolConstraintNeverElapses);
+        };
+        #[cfg(feature = "fan")]
+        if never_elapses(self.fan_min_run_seconds, self.fan_min_recover_seconds)
+            || never_elapses(None, self.fan_manual_off_recover_seconds)
+            || IntoIterator::into_iter([
+                self.start_stagger_seconds,
+                self.emergency_fan_overrun_seconds,
+                self.heat_fan_config.purge_seconds,
+                self.heat_fan_config.prepurge_seconds,
+                #[cfg(feature = "cool")]
+                self.cool_fan_config.purge_seconds,
+                #[cfg(feature = "cool")]
+                self.cool_fan_config.prepurge_seconds,
+                #[cfg(feature = "cool")]
+                self.cool_coil_overrun
+                    .map_or(0, |(_, max_seconds)| max_seconds),
+            ])
+            .any(|seconds| seconds == u32::MAX)
+        {
+            return Err(HvacConfigError::FanConstraintNeverElapses);
+        };
+        #[cfg(feature = "cool")]
+        if never_elapses(
+            self.heat_to_cool_delay_seconds,
+            self.cool_to_heat_delay_seconds,
+        ) || self.auto_hold_seconds == u32::MAX
+        {
+            return Err(HvacConfigError::ChangeoverDelayNeverElapses);
+        };
+        #[cfg(feature = "erv")]
+        if never_elapses(self.erv_min_run_seconds, self.erv_min_recover_seconds) {
+            return Err(HvacConfigError::ErvConstraintNeverElapses);
+        };
+        #[cfg(feature = "cool")]
+        if never_elapses(None, self.shared_compressor_recover_seconds) {
+            return Err(HvacConfigError::SharedCompressorRecoverNeverElapses);
+        };
+        if self.startup_delay_seconds == u32::MAX {
+            return Err(HvacConfigError::StartupDelayNeverElapses);
+        };
+        if IntoIterator::into_iter([
+            self.call_debounce_seconds,
+            self.heat_min_call_seconds,
+            #[cfg(feature = "cool")]
+            self.cool_min_call_seconds,
+        ])
+        .any(|seconds| seconds == u32::MAX)
+        {
+            return Err(HvacConfigError::CallDebounceNeverElapses);
+        };
+        #[cfg(feature = "cool")]
+        if self.valve_settle_seconds == u32::MAX {
+            return Err(HvacConfigError::ValveSettleNeverElapses);
+        };
+        #[cfg(feature = "fan")]
+        if self.circulate.is_some_and(|(on_seconds, off_seconds)| {
+            on_seconds
+                .checked_add(off_seconds)
+                .is_none_or(|period| period == 0)
+        }) {
+            return Err(HvacConfigError::CirculateNeverCycles);
+        };
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        if never_elapses(None, self.economizer_yield_seconds) {
+            return Err(HvacConfigError::EconomizerYieldNeverElapses);
+        };
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        if self
+            .freeze_protection
+            .is_some_and(|(pause_below, resume_above)| pause_below > resume_above)
+        {
+            return Err(HvacConfigError::FreezeProtectionInverted);
+        };
+        if self.time_scale_numerator == 0 && self.time_scale_denominator != 0 {
+            return Err(HvacConfigError::TimeScaleStopsTime);
+        };
+        Ok(())
+    }
+
+    pub(crate) fn state(&self) -> HvacState {
+        HvacState {
+            service: self.output_service(),
+            #[cfg(feature = "fan")]
+            fan: self.fan_active,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            economizing: self.economizing,
+            #[cfg(all(feature = "fan", feature = "cool"))]
+            freeze_protecting: self.freeze_protecting,
+            #[cfg(feature = "erv")]
+            erv: self.erv_active,
+            powered: self.powered,
+            #[cfg(feature = "stats")]
+            short_cycling: self.short_cycling,
+            #[cfg(feature = "stats")]
+            capacity_warning: self.capacity_warning,
+            undersampled: self.undersampled(),
+            starting_up: self.startup_wait().is_some(),
+            #[cfg(feature = "cool")]
+            valve_settling: self.valve_settle_wait().is_some(),
+        }
+    }
+
+    // active service as driven on the outputs, which excludes cool paused by freeze protection
+    fn output_service(&self) -> Option<HvacService> {
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        if self.freeze_protecting {
+            return None;
+        };
+        self.active_service
+    }
+
+    fn block_reason(
+        &self,
+        service: HvacService,
+        calling: bool,
+        wait_seconds: Option<u32>,
+    ) -> Option<BlockReason> {
+        if !calling || self.active_service == Some(service) {
+            return None;
+        };
+        #[cfg(feature = "cool")]
+        if self.active_service.is_some() {
+            return Some(BlockReason::OpposingMinRun);
+        };
+        if self
+            .startup_wait()
+            .is_some_and(|startup| wait_seconds.is_none_or(|wait| startup >= wait))
+        {
+            return Some(BlockReason::StartupDelay);
+        };
+        #[cfg(feature = "cool")]
+        if self
+            .changeover_wait(service)
+            .is_some_and(|changeover| wait_seconds.is_none_or(|wait| changeover >= wait))
+        {
+            return Some(BlockReason::ChangeoverDelay);
+        };
+        if wait_seconds.is_some() {
+            return Some(BlockReason::MinRecover);
+        };
+        #[cfg(feature = "cool")]
+        if self.changeover_held(service) {
+            return Some(BlockReason::AutoHold);
+        };
+        #[cfg(feature = "cool")]
+        if self.valve_service == Some(service) && self.valve_settle_wait().is_some() {
+            return Some(BlockReason::ValveSettle);
+        };
+        self.fan_block_reason(service)
+    }
+
+    #[cfg(feature = "fan")]
+    fn fan_block_reason(&self, service: HvacService) -> Option<BlockReason> {
+        if self.uses_fan(service) && !self.fan_active {
+            Some(BlockReason::FanMinRecover)
+        } else if !self.start_stagger_elapsed() {
+            Some(BlockReason::StartStagger)
+        } else {
+            None
+        }
+    }
+
+    #[cfg(not(feature = "fan"))]
+    fn fan_block_reason(&self, _service: HvacService) -> Option<BlockReason> {
+        None
+    }
+
+    #[cfg(feature = "setpoint")]
+    fn update_recovery(&mut self) {
+        let (setpoint, now) = match (self.scheduled_setpoint, self.last_update) {
+            (Some(setpoint), Some(now)) => (setpoint, now),
+            _ => {
+                self.recovery_service = None;
+                return;
+            }
+        };
+        let recovery_service = if now < setpoint.seconds {
+            let deadband = if self.occupied {
+                0
+            } else {
+                self.unoccupied_deadband.max(0).unsigned_abs().into()
+            };
+            self.temperature_history.recovery_service(
+                setpoint,
+                now,
+                self.recovery_service,
+                deadband,
+            )
+        } else {
+            None
+        };
+        // recovery is complete once its scheduled time arrives or it reaches its setpoint
+        if now >= setpoint.seconds
+            || (self.recovery_service.is_some() && recovery_service != self.recovery_service)
+        {
+            self.scheduled_setpoint = None;
+            self.recovery_service = None;
+        } else {
+            self.recovery_service = recovery_service;
+        };
+    }
+
+    #[cfg(feature = "cool")]
+    fn call(&mut self, service: HvacService) {
+        let last_wins = self.conflict_policy == ConflictPolicy::LastWins;
+        self.testing = false;
+        if self.ignores(service) {
+            return;
+        };
+        match service {
+            HvacService::Heat => {
+                self.heat_calling = true;
+                self.cool_calling = self.cool_calling && !last_wins;
+            }
+            HvacService::Cool => {
+                self.heat_calling = self.heat_calling && !last_wins;
+                self.cool_calling = true;
+            }
+        };
+    }
+
+    #[cfg(not(feature = "cool"))]
+    fn call(&mut self, service: HvacService) {
+        self.testing = false;
+        match service {
+            HvacService::Heat => self.heat_calling = true,
+        };
+    }
+
+    #[cfg(feature = "fan")]
+    fn set_fan_auto(&mut self, fan_auto: bool) {
+        if !fan_auto {
+            self.fan_manual_released = false;
+        } else if !self.fan_auto {
+            self.fan_manual_released = true;
+        };
+        self.fan_auto = fan_auto;
+    }
+
+    // fan in auto with neither a subsystem nor circulation requesting it run
+    #[cfg(feature = "fan")]
+    fn fan_auto_unrequested(&self) -> bool {
+        self.fan_auto
+            && self.fan_requests == 0
+            && !matches!(self.circulate_phase(), Some(CirculatePhase::On(_)))
+    }
+
+    // call for `service` disregarded by a heat-only install
+    #[cfg(feature = "cool")]
+    fn ignores(&self, service: HvacService) -> bool {
+        self.heat_only && service == HvacService::Cool
+    }
+
+    #[cfg(feature = "cool")]
+    fn calls(&self) -> (bool, bool) {
+        if self.testing {
+            return (self.heat_calling, self.cool_calling);
+        };
+        match (self.heat_calling, self.cool_calling) {
+            (true, true) => match self.conflict_policy {
+                ConflictPolicy::LastWins | ConflictPolicy::HeatPriority => (true, false),
+                ConflictPolicy::CoolPriority => (false, true),
+                ConflictPolicy::Ignore => (false, false),
+            },
+            #[cfg(feature = "setpoint")]
+            (false, false) => (
+                self.recovery_service == Some(HvacService::Heat),
+                self.recovery_service == Some(HvacService::Cool) && !self.heat_only,
+            ),
+            calls => calls,
+        }
+    }
+
+    // heat called for, either explicitly or by scheduled setpoint recovery
+    #[cfg(not(feature = "cool"))]
+    fn heat_called(&self) -> bool {
+        #[cfg(feature = "setpoint")]
+        let recovering = self.recovery_service == Some(HvacService::Heat);
+        #[cfg(not(feature = "setpoint"))]
+        let recovering = false;
+        self.heat_calling || recovering
+    }
+
+    #[cfg(feature = "cool")]
+    fn changeover_wait(&self, service: HvacService) -> Option<u32> {
+        if self.active_service.is_some() {
+            return None;
+        };
+        match (self.last_service, service) {
+            (Some(HvacService::Heat), HvacService::Cool) => wait_seconds(
+                self.last_update,
+                self.heat_to_cool_delay_seconds,
+                self.heat_last_stop_seconds,
+            ),
+            (Some(HvacService::Cool), HvacService::Heat) => wait_seconds(
+                self.last_update,
+                self.cool_to_heat_delay_seconds,
+                self.cool_last_stop_seconds,
+            ),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(feature = "cool"))]
+    fn changeover_wait(&self, _service: HvacService) -> Option<u32> {
+        None
+    }
+
+    // wait for the reversing valve to settle, counted from when it last changed over
+    #[cfg(feature = "cool")]
+    fn valve_settle_wait(&self) -> Option<u32> {
+        self.valve_changed_seconds.and_then(|changed_at| {
+            wait_seconds(
+                self.last_update,
+                Some(self.valve_settle_seconds).filter(|&seconds| seconds > 0),
+                Some(changed_at),
+            )
+        })
+    }
+
+    // seconds elapsed at which the oldest start keeping the short cycling warning raised ages
+    // out of its window
+    #[cfg(feature = "stats")]
+    fn short_cycle_expiry(&self) -> Option<u32> {
+        let (max_starts, window_seconds) =
+            self.short_cycle_warning.filter(|_| self.short_cycling)?;
+        let from = self.last_update?.saturating_sub(window_seconds);
+        IntoIterator::into_iter([
+            HvacService::Heat,
+            #[cfg(feature = "cool")]
+            HvacService::Cool,
+        ])
+        .filter(|&service| self.run_history.starts_since(service, from) > max_starts.into())
+        .filter_map(|service| self.run_history.oldest_start_since(service, from))
+        .min()
+        .map(|start| start.saturating_add(window_seconds).saturating_add(1))
+    }
+
+    // wait for the startup delay, counted from the first tick
+    fn startup_wait(&self) -> Option<u32> {
+        wait_seconds(
+            self.last_update,
+            Some(self.startup_delay_seconds).filter(|&seconds| seconds > 0),
+            self.first_update,
+        )
+    }
+
+    // seconds a call for `service` must persist before it is acted on
+    fn call_debounce_seconds(&self, service: HvacService) -> u32 {
+        let min_call_seconds = match service {
+            HvacService::Heat => self.heat_min_call_seconds,
+            #[cfg(feature = "cool")]
+            HvacService::Cool => self.cool_min_call_seconds,
+        };
+        self.call_debounce_seconds.max(min_call_seconds)
+    }
+
+    // wait for a call to persist for the debounce time, counted from when it was first made
+    fn call_debounce_wait(&self, service: HvacService) -> Option<u32> {
+        if self.active_service == Some(service) {
+            return None;
+        };
+        let called_since = match service {
+            HvacService::Heat => self.heat_called_since,
+            #[cfg(feature = "cool")]
+            HvacService::Cool => self.cool_called_since,
+        };
+        wait_seconds(
+            self.last_update,
+            Some(self.call_debounce_seconds(service)).filter(|&seconds| seconds > 0),
+            called_since,
+        )
+    }
+
+    // recover wait of a compressor shared by heat and cool, counted from whichever stopped last
+    #[cfg(feature = "cool")]
+    fn shared_compressor_wait(&self) -> Option<u32> {
+        if self.active_service.is_some() {
+            return None;
+        };
+        wait_seconds(
+            self.last_update,
+            self.shared_compressor_recover_seconds,
+            self.heat_last_stop_seconds.max(self.cool_last_stop_seconds),
+        )
+    }
+
+    #[cfg(not(feature = "cool"))]
+    fn shared_compressor_wait(&self) -> Option<u32> {
+        None
+    }
+
+    fn undersampled(&self) -> bool {
+        let interval = match self.tick_interval_seconds {
+            Some(interval) => interval,
+            None => return false,
+        };
+        #[cfg(feature = "fan")]
+        let fan = [self.fan_min_run_seconds, self.fan_min_recover_seconds];
+        #[cfg(not(feature = "fan"))]
+        let fan = [None, None];
+        self.tick_late
+            || IntoIterator::into_iter([
+                self.heat_min_run_seconds,
+                self.heat_min_recover_seconds,
+                #[cfg(feature = "cool")]
+                self.cool_min_run_seconds,
+                #[cfg(feature = "cool")]
+                self.cool_min_recover_seconds,
+                #[cfg(feature = "erv")]
+                self.erv_min_run_seconds,
+                #[cfg(feature = "erv")]
+                self.erv_min_recover_seconds,
+            ])
+            .chain(IntoIterator::into_iter(fan))
+            .flatten()
+            .any(|seconds| seconds > 0 && seconds < interval)
+    }
+
+    #[cfg(feature = "cool")]
+    fn changeover_held(&self, service: HvacService) -> bool {
+        self.last_service == Some(service.opposite())
+            && self.last_changeover_seconds.is_some_and(|at| {
+                self.last_update.unwrap_or(0) < at.saturating_add(self.auto_hold_seconds)
+            })
+    }
+
+    #[cfg(not(feature = "cool"))]
+    fn changeover_held(&self, _service: HvacService) -> bool {
+        false
+    }
+
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    fn cool_coil_overrun_active(&self) -> bool {
+        match (self.cool_coil_overrun, self.cool_last_stop_seconds) {
+            (Some((stop_above, max_seconds)), Some(stopped_at))
+                if self.active_service.is_none()
+                    && self.last_service == Some(HvacService::Cool) =>
+            {
+                self.last_update.unwrap_or(0) < stopped_at.saturating_add(max_seconds)
+                    && self
+                        .coil_temp
+                        .is_none_or(|coil_temp| coil_temp <= stop_above)
+            }
+            _ => false,
+        }
+    }
+
+    // end of the cool coil overrun while it is keeping the fan on
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    fn coil_overrun_until(&self) -> Option<u32> {
+        match (self.cool_coil_overrun, self.cool_last_stop_seconds) {
+            (Some((_, max_seconds)), Some(stopped_at)) if self.cool_coil_overrun_active() => {
+                Some(stopped_at.saturating_add(max_seconds))
+            }
+            _ => None,
+        }
+    }
+
+    #[cfg(all(feature = "fan", not(feature = "cool")))]
+    fn coil_overrun_until(&self) -> Option<u32> {
+        None
+    }
+
+    // if the fan is running to economize, which only ever serves a call for cool
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    fn economizing(&self) -> bool {
+        self.economizing
+    }
+
+    #[cfg(all(feature = "fan", not(feature = "cool")))]
+    fn economizing(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "fan")]
+    fn start_stagger_elapsed(&self) -> bool {
+        self.start_stagger_until
+            .is_none_or(|until| self.last_update.unwrap_or(0) >= until)
+    }
+
+    #[cfg(feature = "fan")]
+    fn start_fan_with_service(&mut self, service: HvacService) {
+        if !self.fan_active && self.fan_wait_seconds.is_none() {
+            self.fan_last_start_seconds = self.last_update;
+            self.fan_active = true;
+            let stagger_seconds = self.start_stagger_seconds(service);
+            if stagger_seconds > 0 {
+                self.start_stagger_until = Some(
+                    self.last_update
+                        .unwrap_or(0)
+                        .saturating_add(stagger_seconds),
+                );
+            };
+        };
+    }
+
+    #[cfg(feature = "fan")]
+    fn fan_config(&self, service: HvacService) -> ServiceFanConfig {
+        match service {
+            HvacService::Heat => self.heat_fan_config,
+            #[cfg(feature = "cool")]
+            HvacService::Cool => self.cool_fan_config,
+        }
+    }
+
+    #[cfg(feature = "fan")]
+    fn uses_fan(&self, service: HvacService) -> bool {
+        self.fan_config(service).coupled
+    }
+
+    // delay between the fan and `service` starting together, the longer of the start stagger
+    // and the service's pre-purge
+    #[cfg(feature = "fan")]
+    fn start_stagger_seconds(&self, service: HvacService) -> u32 {
+        self.start_stagger_seconds
+            .max(self.fan_config(service).prepurge_seconds)
+    }
+
+    // if the fan should already be running for an armed call, starting it only within the
+    // service's start stagger plus any remaining fan recover before the call is made
+    #[cfg(feature = "fan")]
+    fn armed_prewarm(&self) -> bool {
+        self.armed.is_some_and(|(service, at_seconds)| {
+            let fan_recover = if self.fan_active {
+                None
+            } else {
+                wait_seconds(
+                    self.last_update,
+                    self.effective_fan_recover(),
+                    self.fan_last_stop_seconds,
+                )
+            };
+            let lead = self
+                .start_stagger_seconds(service)
+                .saturating_add(fan_recover.unwrap_or(0));
+            self.uses_fan(service)
+                && self.last_update.unwrap_or(0).saturating_add(lead) >= at_seconds
+        })
+    }
+
+    // end of the purge after the most recent service stopped, while it has yet to elapse
+    #[cfg(feature = "fan")]
+    fn fan_purge_until(&self) -> Option<u32> {
+        if self.active_service.is_some() {
+            return None;
+        };
+        let service = self.last_service?;
+        let last_stop = match service {
+            HvacService::Heat => self.heat_last_stop_seconds,
+            #[cfg(feature = "cool")]
+            HvacService::Cool => self.cool_last_stop_seconds,
+        };
+        let purge_seconds = self.fan_config(service).purge_seconds;
+        Some(last_stop?.saturating_add(purge_seconds))
+            .filter(|&until| purge_seconds > 0 && until > self.last_update.unwrap_or(0))
+    }
+
+    #[cfg(feature = "fan")]
+    fn active_service_uses_fan(&self) -> bool {
+        self.active_service
+            .is_some_and(|service| self.uses_fan(service))
+    }
+
+    // fan held on while idle between one service and a call for the opposing service
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    fn fan_held_for_changeover(&self) -> bool {
+        let (heat_called, cool_called) = self.calls();
+        self.fan_continuous_on_changeover
+            && self.powered
+            && !self.emergency_stopped
+            && self.active_service.is_none()
+            && IntoIterator::into_iter([
+                (HvacService::Heat, heat_called),
+                (HvacService::Cool, cool_called),
+            ])
+            .any(|(service, called)| {
+                called && self.uses_fan(service) && self.last_service == Some(service.opposite())
+            })
+    }
+
+    #[cfg(all(feature = "fan", not(feature = "cool")))]
+    fn fan_held_for_changeover(&self) -> bool {
+        false
+    }
+
+    fn start_service(&mut self, service: HvacService) {
+        #[cfg(feature = "fan")]
+        {
+            if self.uses_fan(service) {
+                self.start_fan_with_service(service);
+                if !self.fan_active || !self.start_stagger_elapsed() {
+                    return;
+                };
+            };
+            self.start_stagger_until = None;
+        }
+        #[cfg(feature = "cool")]
+        {
+            if self.valve_settle_seconds > 0 && self.valve_service == Some(service.opposite()) {
+                self.valve_changed_seconds = self.last_update;
+            };
+            self.valve_service = Some(service);
+            if self.valve_settle_wait().is_some() {
+                return;
+            };
+            if self.last_service.is_some_and(|last| last != service) {
+                self.last_changeover_seconds = Some(self.last_update.unwrap_or(0));
+            };
+        }
+        self.last_service = Some(service);
+        match service {
+            HvacService::Heat => {
+                self.heat_last_start_seconds = self.last_update;
+                self.run_min_run_override = self.heat_min_run_override.take();
+            }
+            #[cfg(feature = "cool")]
+            HvacService::Cool => {
+                self.cool_last_start_seconds = self.last_update;
+                self.run_min_run_override = self.cool_min_run_override.take();
+            }
+        };
+        self.active_service = Some(service);
+    }
+
+    // min run of the active run, which may be overridden for that run alone
+    fn run_min_run(&self, min_run_seconds: Option<u32>) -> Option<u32> {
+        self.run_min_run_override.or(min_run_seconds)
+    }
+
+    fn scaled_seconds(&self, seconds: u32) -> u32 {
+        if self.time_scale_numerator == self.time_scale_denominator
+            || self.time_scale_denominator == 0
+        {
+            seconds
+        } else {
+            let scaled = u64::from(seconds) * u64::from(self.time_scale_numerator)
+                / u64::from(self.time_scale_denominator);
+            if scaled > u64::from(u32::MAX) {
+                u32::MAX
+            } else {
+                scaled as u32
+            }
+        }
+    }
+
+    #[cfg(feature = "fan")]
+    fn fan_speed_target(&self) -> u8 {
+        if !self.fan_active {
+            return 0;
+        };
+        #[cfg(feature = "cool")]
+        let target = match (self.humidity_fan_modulation, self.humidity_rh) {
+            (Some((high_rh_speed, low_rh_speed, threshold)), Some(rh))
+                if self.output_service() == Some(HvacService::Cool) =>
+            {
+                if rh >= threshold {
+                    high_rh_speed
+                } else {
+                    low_rh_speed
+                }
+            }
+            _ => u8::MAX,
+        };
+        #[cfg(not(feature = "cool"))]
+        let target = u8::MAX;
+        target
+    }
+
+    fn min_run_satisfied(&self, wait_seconds: Option<u32>) -> bool {
+        wait_seconds.is_none_or(|wait| wait <= self.min_run_grace_seconds)
+    }
+
+    fn compute(&mut self) -> HvacState {
+        if self.auto_zero_on_first_call && self.last_update.is_none() {
+            self.last_update = Some(0);
+            self.first_update = Some(0);
+        };
+        let previous = self.state();
+        #[cfg(feature = "stats")]
+        let previous_service = self.active_service;
+        if let Some((service, at_seconds)) = self.armed {
+            if self.last_update.unwrap_or(0) >= at_seconds {
+                self.armed = None;
+                self.call(service);
+            };
+        };
+        #[cfg(feature = "setpoint")]
+        self.update_recovery();
+        let emergency = self.emergency_stopped;
+        #[cfg(feature = "cool")]
+        let (heat_called, cool_called) = self.calls();
+        #[cfg(not(feature = "cool"))]
+        let heat_called = self.heat_called();
+        self.heat_called_since = heat_called
+            .then(|| self.heat_called_since.or(self.last_update))
+            .flatten();
+        let heat_debouncing =
+            heat_called && !self.testing && self.call_debounce_wait(HvacService::Heat).is_some();
+        let heat_calling = heat_called && !heat_debouncing && !emergency && self.powered;
+
+        #[cfg(feature = "cool")]
+        {
+            self.cool_called_since = cool_called
+                .then(|| self.cool_called_since.or(self.last_update))
+                .flatten();
+        }
+        #[cfg(feature = "cool")]
+        let cool_debouncing =
+            cool_called && !self.testing && self.call_debounce_wait(HvacService::Cool).is_some();
+        #[cfg(feature = "cool")]
+        let cool_requested = cool_called && !cool_debouncing && !emergency && self.powered;
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        let economizing = {
+            self.economizer_cool_since = if self.economize && cool_requested {
+                self.economizer_cool_since.or(self.last_update)
+            } else {
+                None
+            };
+            let economizer_yielded = match (
+                self.economizer_yield_seconds,
+                self.economizer_cool_since,
+                self.last_update,
+            ) {
+                (Some(yield_seconds), Some(since), Some(now)) => {
+                    now.saturating_sub(since) >= yield_seconds
+                }
+                _ => false,
+            };
+            self.economize
+                && !self.testing
+                && !emergency
+                && self.powered
+                && !heat_calling
+                && !economizer_yielded
+        };
+        #[cfg(any(
+            all(feature = "fan", not(feature = "cool")),
+            all(feature = "cool", not(feature = "fan"))
+        ))]
+        let economizing = false;
+        #[cfg(feature = "cool")]
+        let humidity_locked = match (self.cool_humidity_lockout_rh, self.humidity_rh) {
+            (Some(min_rh), Some(rh)) => rh < min_rh && !self.testing,
+            _ => false,
+        };
+        #[cfg(feature = "cool")]
+        let cool_calling = cool_requested && !economizing && !humidity_locked;
+        #[cfg(all(feature = "fan", not(feature = "cool")))]
+        let cool_calling = false;
+        #[cfg(feature = "fan")]
+        let fan_auto =
+            (self.fan_auto_unrequested() && !economizing && !self.armed_prewarm()) || !self.powered;
+        #[cfg(feature = "fan")]
+        if !heat_calling && !cool_calling {
+            self.start_stagger_until = None;
+        };
+
+        self.heat_wait_seconds = if self.active_service == Some(HvacService::Heat) {
+            wait_seconds(
+                self.last_update,
+                self.run_min_run(self.heat_min_run_seconds),
+                self.heat_last_start_seconds,
+            )
+        } else {
+            wait_seconds(
+                self.last_update,
+                self.effective_heat_recover(),
+                self.heat_last_stop_seconds,
+            )
+            .max(self.changeover_wait(HvacService::Heat))
+            .max(self.shared_compressor_wait())
+            .max(self.startup_wait())
+        };
+
+        #[cfg(feature = "cool")]
+        {
+            self.cool_wait_seconds = if self.active_service == Some(HvacService::Cool) {
+                wait_seconds(
+                    self.last_update,
+                    self.run_min_run(self.cool_min_run_seconds),
+                    self.cool_last_start_seconds,
+                )
+            } else {
+                wait_seconds(
+                    self.last_update,
+                    self.effective_cool_recover(),
+                    self.cool_last_stop_seconds,
+                )
+                .max(self.changeover_wait(HvacService::Cool))
+                .max(self.shared_compressor_wait())
+                .max(self.startup_wait())
+            };
+        }
+
+        #[cfg(feature = "fan")]
+        {
+            self.fan_wait_seconds = if self.fan_active {
+                wait_seconds(
+                    self.last_update,
+                    self.fan_min_run_seconds,
+                    self.fan_last_start_seconds,
+                )
+            } else {
+                wait_seconds(
+                    self.last_update,
+                    self.effective_fan_recover(),
+                    self.fan_last_stop_seconds,
+                )
+            };
+        }
+
+        #[cfg(feature = "erv")]
+        {
+            self.erv_wait_seconds = if self.erv_active {
+                wait_seconds(
+                    self.last_update,
+                    self.erv_min_run_seconds,
+                    self.erv_last_start_seconds,
+                )
+            } else {
+                wait_seconds(
+                    self.last_update,
+                    self.erv_min_recover_seconds,
+                    self.erv_last_stop_seconds,
+                )
+            };
+
+            let erv_requested = self.erv_requested && !emergency && self.powered;
+            if self.erv_active != erv_requested && (self.erv_wait_seconds.is_none() || emergency) {
+                if erv_requested {
+                    self.erv_last_start_seconds = self.last_update;
+                } else {
+                    self.erv_last_stop_seconds = self.last_update;
+                };
+                self.erv_active = erv_requested;
+            };
+        }
+
+        match self.active_service {
+            Some(HvacService::Heat)
+                if !heat_calling
+                    && (emergency || self.min_run_satisfied(self.heat_wait_seconds)) =>
+            {
+                self.heat_last_stop_seconds = self.last_update;
+                self.active_service = None;
+            }
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool)
+                if !cool_calling
+                    && (emergency || self.min_run_satisfied(self.cool_wait_seconds)) =>
+            {
+                self.cool_last_run_completed = self
+                    .last_update
+                    .unwrap_or(0)
+                    .saturating_sub(self.cool_last_start_seconds.unwrap_or(0))
+                    .saturating_add(self.min_run_grace_seconds)
+                    >= self.run_min_run(self.cool_min_run_seconds).unwrap_or(0);
+                self.cool_last_stop_seconds = self.last_update;
+                self.active_service = None;
+            }
+            _ => {}
+        };
+
+        if self.active_service.is_none() {
+            if heat_calling
+                && self.heat_wait_seconds.is_none()
+                && self.changeover_wait(HvacService::Heat).is_none()
+                && !self.changeover_held(HvacService::Heat)
+            {
+                self.start_service(HvacService::Heat);
+            } else {
+                #[cfg(feature = "cool")]
+                if cool_calling
+                    && self.cool_wait_seconds.is_none()
+                    && self.changeover_wait(HvacService::Cool).is_none()
+                    && !self.changeover_held(HvacService::Cool)
+                {
+                    self.start_service(HvacService::Cool);
+                };
+            };
+        };
+
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        {
+            self.freeze_protecting = match (self.freeze_protection, self.coil_temp) {
+                (Some((pause_below, resume_above)), Some(coil_temp))
+                    if self.active_service == Some(HvacService::Cool) =>
+                {
+                    if coil_temp < pause_below {
+                        true
+                    } else if coil_temp > resume_above {
+                        false
+                    } else {
+                        self.freeze_protecting
+                    }
+                }
+                _ => false,
+            };
+        }
+        #[cfg(feature = "fan")]
+        {
+            if self.active_service_uses_fan() {
+                self.fan_manual_released = false;
+            };
+            if self.fan_active && (fan_auto || emergency) {
+                // min run is checked afresh rather than via the wait computed above, which only
+                // reflects min run if the fan was already running when this compute began
+                let stop = if emergency {
+                    self.emergency_purge_until
+                        .is_none_or(|until| self.last_update.unwrap_or(0) >= until)
+                } else {
+                    !self.active_service_uses_fan()
+                        && !self.fan_held_for_changeover()
+                        && self.fan_min_run_remaining().is_none()
+                        && self.start_stagger_elapsed()
+                        && self.fan_purge_until().is_none()
+                        && self.coil_overrun_until().is_none()
+                };
+                if stop {
+                    self.fan_last_stop_seconds = self.last_update;
+                    self.fan_last_stop_manual = self.fan_manual_released;
+                    self.fan_manual_released = false;
+                    self.fan_active = false;
+                };
+            } else if !fan_auto && !emergency && !self.fan_active && self.fan_wait_seconds.is_none()
+            {
+                self.fan_last_start_seconds = self.last_update;
+                self.fan_active = true;
+            };
+        }
+
+        self.heat_block_reason =
+            self.block_reason(HvacService::Heat, heat_calling, self.heat_wait_seconds);
+        if heat_debouncing {
+            self.heat_block_reason = Some(BlockReason::CallDebounce);
+        };
+        if emergency {
+            self.heat_block_reason = heat_called.then_some(BlockReason::EmergencyStop);
+        } else if !self.powered {
+            self.heat_block_reason = heat_called.then_some(BlockReason::PoweredOff);
+        };
+        #[cfg(feature = "cool")]
+        {
+            self.cool_block_reason = if humidity_locked && cool_requested {
+                Some(BlockReason::HumidityLockout)
+            } else {
+                self.block_reason(HvacService::Cool, cool_calling, self.cool_wait_seconds)
+            };
+            if cool_debouncing {
+                self.cool_block_reason = Some(BlockReason::CallDebounce);
+            };
+            if emergency {
+                self.cool_block_reason = cool_called.then_some(BlockReason::EmergencyStop);
+            } else if !self.powered {
+                self.cool_block_reason = cool_called.then_some(BlockReason::PoweredOff);
+            };
+            if self.heat_calling && !heat_called {
+                self.heat_block_reason = Some(BlockReason::ConflictPolicy);
+            };
+            if self.cool_calling && !cool_called {
+                self.cool_block_reason = Some(BlockReason::ConflictPolicy);
+            };
+        }
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        {
+            if economizing && cool_requested {
+                self.cool_block_reason = Some(BlockReason::Economizer);
+            };
+            self.economizing = economizing && self.active_service.is_none() && self.fan_active;
+        }
+        #[cfg(feature = "fan")]
+        if self.fan_ramp_step == 0 {
+            self.fan_speed = self.fan_speed_target();
+        };
+        self.dirty = false;
+        self.held_service = match self.active_service {
+            Some(HvacService::Heat) if !heat_calling => Some(HvacService::Heat),
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) if !cool_calling => Some(HvacService::Cool),
+            _ => None,
+        };
+
+        #[cfg(feature = "stats")]
+        self.update_stats(previous_service, previous.service);
+
+        let state = self.state();
+        if let Some(TransitionHook(hook)) = self.transition_hook {
+            if state != previous {
+                hook(HvacTransition {
+                    from: previous,
+                    to: state,
+                    at_seconds: self.last_update.unwrap_or(0),
+                });
+            };
+        };
+        state
+    }
+
+    #[cfg(feature = "stats")]
+    fn update_stats(
+        &mut self,
+        previous_service: Option<HvacService>,
+        previous_output_service: Option<HvacService>,
+    ) {
+        if self.active_service != previous_service {
+            if let Some(service) = self.active_service {
+                self.runtime_stats.start(service);
+            };
+        };
+        // run history follows the outputs, so time paused by freeze protection is not a run
+        let output_service = self.output_service();
+        if output_service != previous_output_service {
+            let now = self.last_update.unwrap_or(0);
+            if previous_output_service.is_some() {
+                self.run_history.stop(now);
+            };
+            if let Some(service) = output_service {
+                self.run_history.start(service, now);
+            };
+        };
+        self.short_cycling = match (self.short_cycle_warning, self.last_update) {
+            (Some((max_starts, window_seconds)), Some(now)) => {
+                let from = now.saturating_sub(window_seconds);
+                IntoIterator::into_iter([
+                    HvacService::Heat,
+                    #[cfg(feature = "cool")]
+                    HvacService::Cool,
+                ])
+                .any(|service| self.run_history.starts_since(service, from) > max_starts.into())
+            }
+            _ => false,
+        };
+        self.capacity_warning = match (self.capacity_warning_seconds, output_service) {
+            (Some(seconds), Some(service)) if self.held_service.is_none() => self
+                .continuous_run_seconds(service)
+                .is_some_and(|run_seconds| run_seconds >= seconds),
+            _ => false,
+        };
+    }
+
+    /// output state of a redundant pair of controllers, using `primary` unless it is faulted
+    pub fn merge(primary: &Hvac, backup: &Hvac, primary_ok: bool) -> HvacState {
+        if primary_ok {
+            primary.state()
+        } else {
+            backup.state()
+        }
+    }
+
+    /// polarity-adjusted output levels for driving each terminal
+    pub fn raw_outputs(&self) -> RawOutputs {
+        RawOutputs {
+            heat: (self.output_service() == Some(HvacService::Heat)) != self.heat_inverted,
+            #[cfg(feature = "cool")]
+            cool: (self.output_service() == Some(HvacService::Cool)) != self.cool_inverted,
+            #[cfg(feature = "fan")]
+            fan: self.fan_active != self.fan_inverted,
+        }
+    }
+
+    /// if a compressor-driven service is active, with heat assumed to come from a heat pump
+    pub fn compressor_active(&self) -> bool {
+        self.output_service().is_some()
+    }
+
+    /// fan speed from 0 (off) to 255 (full), ramped toward full while the fan is active and toward
+    /// off otherwise, or toward the humidity modulated speed while cooling, see
+    /// `with_humidity_fan_modulation`
+    #[cfg(feature = "fan")]
+    pub fn fan_speed(&self) -> u8 {
+        self.fan_speed
+    }
+
+    /// off time, not a countdown, that will apply once cool next stops, assuming it completes
+    /// its cycle
+    ///
+    /// this is the longest of the min recover and post run off times and, while cool is active,
+    /// any proportional recover for its run up to the last tick. a cycle cut short of its min run,
+    /// such as by `emergency_stop`, skips the post run off time, see `effective_cool_recover`.
+    #[cfg(feature = "cool")]
+    pub fn cool_post_stop_recover(&self) -> Option<u32> {
+        let proportional_recover = if self.active_service == Some(HvacService::Cool) {
+            proportional_recover_seconds(
+                self.cool_recover_ratio_percent,
+                self.cool_last_start_seconds,
+                self.last_update,
+            )
+        } else {
+            None
+        };
+        self.cool_min_recover_seconds
+            .max(self.cool_post_run_off_seconds)
+            .max(proportional_recover)
+    }
+
+    /// runtime and cycle counts accumulated since the last call, resetting them to zero without
+    /// affecting any timers, e.g. to report a rollup per calendar day
+    ///
+    /// runtime is counted up to the last tick.
+    #[cfg(feature = "stats")]
+    pub fn take_runtime_stats(&mut self) -> RuntimeStats {
+        core::mem::take(&mut self.runtime_stats)
+    }
+
+    /// if a call for `service` made now would have it active without waiting for another tick,
+    /// leaving this state machine untouched
+    ///
+    /// every gating condition is evaluated against the last tick, including recover timers,
+    /// changeover constraints, and the fan. an already active service reports `true`.
+    pub fn would_start(&self, service: HvacService) -> bool {
+        let mut hvac = *self;
+        hvac.transition_hook = None;
+        hvac.strict = false;
+        let state = match service {
+            HvacService::Heat => hvac.heat(),
+            #[cfg(feature = "cool")]
+            HvacService::Cool => hvac.cool(),
+        };
+        state.service == Some(service)
+    }
+
+    /// upper bound on the seconds a call for `service` made now could wait before it starts,
+    /// from the configuration and current timers
+    ///
+    /// every gate that could hold the call is summed, including the opposing service's remaining
+    /// min run, changeover delay and auto hold, recover times, and the fan's recover and start
+    /// stagger, though some of these run concurrently in practice. an active service reports 0,
+    /// and `u32::MAX` is reported while powered off or emergency stopped, which have no bound.
+    pub fn max_start_latency(&self, service: HvacService) -> u32 {
+        if self.active_service == Some(service) {
+            return 0;
+        };
+        if !self.powered || self.emergency_stopped {
+            return u32::MAX;
+        };
+        let own_wait = match service {
+            HvacService::Heat => self.heat_wait_seconds,
+            #[cfg(feature = "cool")]
+            HvacService::Cool => self.cool_wait_seconds,
+        };
+        #[cfg_attr(not(any(feature = "fan", feature = "cool")), allow(unused_mut))]
+        let mut latency = own_wait
+            .unwrap_or(0)
+            .saturating_add(self.call_debounce_seconds(service));
+        #[cfg(feature = "cool")]
+        {
+            let changeover_delay = match service {
+                HvacService::Heat => self.cool_to_heat_delay_seconds,
+                HvacService::Cool => self.heat_to_cool_delay_seconds,
+            };
+            if self.active_service.is_some() {
+                // the opposing service must first complete its min run, after which the
+                // changeover and shared compressor waits begin in full
+                let opposing_wait = match service {
+                    HvacService::Heat => wait_seconds(
+                        self.last_update,
+                        self.run_min_run(self.cool_min_run_seconds),
+                        self.cool_last_start_seconds,
+                    ),
+                    HvacService::Cool => wait_seconds(
+                        self.last_update,
+                        self.run_min_run(self.heat_min_run_seconds),
+                        self.heat_last_start_seconds,
+                    ),
+                };
+                latency = latency
+                    .saturating_add(opposing_wait.unwrap_or(0))
+                    .saturating_add(changeover_delay.unwrap_or(0))
+                    .saturating_add(self.shared_compressor_recover_seconds.unwrap_or(0));
+            };
+            if self.valve_service == Some(service) {
+                latency = latency.saturating_add(self.valve_settle_wait().unwrap_or(0));
+            } else if self.valve_service.is_some() {
+                latency = latency.saturating_add(self.valve_settle_seconds);
+            };
+            if self.last_service == Some(service.opposite()) {
+                let hold_until = self
+                    .last_changeover_seconds
+                    .map_or(0, |at| at.saturating_add(self.auto_hold_seconds));
+                latency = latency
+                    .saturating_add(hold_until.saturating_sub(self.last_update.unwrap_or(0)));
+            };
+        }
+        #[cfg(feature = "fan")]
+        if self.uses_fan(service) {
+            if !self.fan_active {
+                latency = latency.saturating_add(self.fan_wait_seconds.unwrap_or(0));
+            } else if self.active_service.is_some() {
+                // a fan running with the opposing service may stop along with it, after which
+                // its recover begins in full
+                latency = latency.saturating_add(self.effective_fan_recover().unwrap_or(0));
+            };
+            latency = latency.saturating_add(self.start_stagger_seconds(service));
+        };
+        latency
+    }
+
+    /// service the reversing valve is positioned for, which changes over ahead of the service
+    /// itself while the valve settles, see `with_valve_settle`
+    #[cfg(feature = "cool")]
+    pub fn valve_service(&self) -> Option<HvacService> {
+        self.valve_service
+    }
+
+    /// if every service is off only because a call for the service opposite the last one is
+    /// waiting on its changeover delay, distinguishing that protective gap from having no demand
+    #[cfg(feature = "cool")]
+    pub fn in_changeover_gap(&self) -> bool {
+        let (heat_called, cool_called) = self.calls();
+        self.last_service
+            .map(HvacService::opposite)
+            .is_some_and(|service| {
+                let called = match service {
+                    HvacService::Heat => heat_called,
+                    HvacService::Cool => cool_called,
+                };
+                called && self.changeover_wait(service).is_some()
+            })
+    }
+
+    /// most recently active service, including the active one, which is retained once the system
+    /// goes idle, e.g. to hold a reversing valve in position during the off period
+    pub fn last_service(&self) -> Option<HvacService> {
+        self.last_service
+    }
+
+    /// recover time heat currently waits for after stopping, including any proportional recover
+    /// derived from its last run
+    ///
+    /// this is the full time rather than a countdown, and excludes any changeover delay.
+    pub fn effective_heat_recover(&self) -> Option<u32> {
+        self.heat_min_recover_seconds
+            .max(proportional_recover_seconds(
+                self.heat_recover_ratio_percent,
+                self.heat_last_start_seconds,
+                self.heat_last_stop_seconds,
+            ))
+    }
+
+    /// recover time cool currently waits for after stopping, including any post run off time
+    /// after a completed cycle and any proportional recover derived from its last run
+    ///
+    /// this is the full time rather than a countdown, and excludes any changeover delay.
+    #[cfg(feature = "cool")]
+    pub fn effective_cool_recover(&self) -> Option<u32> {
+        let recover_seconds = if self.cool_last_run_completed {
+            self.cool_min_recover_seconds
+                .max(self.cool_post_run_off_seconds)
+        } else {
+            self.cool_min_recover_seconds
+        };
+        recover_seconds.max(proportional_recover_seconds(
+            self.cool_recover_ratio_percent,
+            self.cool_last_start_seconds,
+            self.cool_last_stop_seconds,
+        ))
+    }
+
+    /// recover time the fan currently waits for after stopping, depending on whether it was
+    /// last stopped automatically or manually
+    ///
+    /// this is the full time rather than a countdown.
+    #[cfg(feature = "fan")]
+    pub fn effective_fan_recover(&self) -> Option<u32> {
+        if self.fan_last_stop_manual {
+            self.fan_manual_off_recover_seconds
+        } else {
+            self.fan_min_recover_seconds
+        }
+    }
+
+    /// seconds elapsed at which heat's recover time will be satisfied, `last_stop + recover`
+    /// using `effective_heat_recover`, or `None` if already satisfied or while heat is active
+    ///
+    /// other gates such as a changeover delay may still hold heat off once it is available.
+    pub fn heat_available_at(&self) -> Option<u32> {
+        self.available_at(
+            self.active_service == Some(HvacService::Heat),
+            self.effective_heat_recover(),
+            self.heat_last_stop_seconds,
+        )
+    }
+
+    /// seconds elapsed at which cool's recover time will be satisfied, see `heat_available_at`
+    #[cfg(feature = "cool")]
+    pub fn cool_available_at(&self) -> Option<u32> {
+        self.available_at(
+            self.active_service == Some(HvacService::Cool),
+            self.effective_cool_recover(),
+            self.cool_last_stop_seconds,
+        )
+    }
+
+    /// seconds elapsed at which the fan's recover time will be satisfied, see `heat_available_at`
+    #[cfg(feature = "fan")]
+    pub fn fan_available_at(&self) -> Option<u32> {
+        self.available_at(
+            self.fan_active,
+            self.effective_fan_recover(),
+            self.fan_last_stop_seconds,
+        )
+    }
+
+    fn available_at(
+        &self,
+        active: bool,
+        recover_seconds: Option<u32>,
+        last_stop: Option<u32>,
+    ) -> Option<u32> {
+        if active {
+            return None;
+        };
+        recover_seconds
+            .map(|recover| last_stop.unwrap_or(0).saturating_add(recover))
+            .filter(|&at| at > self.last_update.unwrap_or(0))
+    }
+
+    /// every logical state the state machine may be in
+    pub fn states() -> &'static [LogicalState] {
+        graph::STATES
+    }
+
+    /// every allowed transition between logical states, with a description of its guard
+    ///
+    /// a single tick or call may take more than one transition, e.g. from idle through fan only
+    /// to heating when no start stagger applies.
+    pub fn transitions() -> &'static [StateTransition] {
+        graph::TRANSITIONS
+    }
+
+    /// current logical state
+    pub fn logical_state(&self) -> LogicalState {
+        match self.active_service {
+            Some(HvacService::Heat) => LogicalState::Heating,
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => LogicalState::Cooling,
+            #[cfg(feature = "fan")]
+            None if self.fan_active => {
+                if self.fan_auto_unrequested()
+                    && !self.economizing()
+                    && self.armed.is_none()
+                    && self.start_stagger_elapsed()
+                {
+                    LogicalState::FanPurge
+                } else {
+                    LogicalState::FanOnly
+                }
+            }
+            None => LogicalState::Idle,
+        }
+    }
+
+    /// current operating mode, suitable for grouping telemetry without timer noise
+    pub fn operating_mode(&self) -> OperatingMode {
+        self.state().operating_mode()
+    }
+
+    /// if the fan is only still on because its minimum run time has not yet elapsed
+    ///
+    /// always `false` before the first tick, as the fan cannot yet have started.
+    #[cfg(feature = "fan")]
+    pub fn fan_held_by_min_run(&self) -> bool {
+        self.fan_active
+            && self.fan_auto_unrequested()
+            && !self.economizing()
+            && self.active_service.is_none()
+            && self.fan_wait_seconds.is_some()
+    }
+
+    /// if the fan is on by its own demand, i.e. the thermostat's g terminal, as opposed to the
+    /// effective `HvacState::fan` which also includes the fan coupled to a service
+    ///
+    /// only manual fan, an outstanding `request_fan` token, or the on phase of circulate mode count
+    /// as demand. the fan remaining on for its min run time after that demand ends does not.
+    #[cfg(feature = "fan")]
+    pub fn commanded_fan(&self) -> bool {
+        self.fan_active && !self.fan_auto_unrequested()
+    }
+
+    /// seconds left on each timer still pending as of the last tick or call
+    ///
+    /// each timer counts down its min run time while its output is on and its min recover time
+    /// while off. before the first tick every timer reports its full min recover time.
+    pub fn pending_waits(&self) -> impl Iterator<Item = (TimerKind, u32)> {
+        let ticked = self.has_ticked();
+        let pending = move |kind, wait_seconds: Option<u32>, min_recover_seconds: Option<u32>| {
+            if ticked {
+                wait_seconds
+            } else {
+                min_recover_seconds
+            }
+            .map(|seconds| (kind, seconds))
+        };
+        #[cfg(feature = "fan")]
+        let fan = pending(
+            TimerKind::Fan,
+            self.fan_wait_seconds,
+            self.fan_min_recover_seconds,
+        );
+        #[cfg(not(feature = "fan"))]
+        let fan = None;
+        IntoIterator::into_iter([
+            pending(
+                TimerKind::Heat,
+                self.heat_wait_seconds,
+                self.heat_min_recover_seconds,
+            ),
+            #[cfg(feature = "cool")]
+            pending(
+                TimerKind::Cool,
+                self.cool_wait_seconds,
+                self.cool_min_recover_seconds,
+            ),
+            fan,
+            #[cfg(feature = "erv")]
+            pending(
+                TimerKind::Erv,
+                self.erv_wait_seconds,
+                self.erv_min_recover_seconds,
+            ),
+        ])
+        .flatten()
+    }
+
+    /// seconds `service` has been running continuously as of the last tick, or `None` while it is
+    /// not active
+    ///
+    /// a pause by freeze protection ends the run, with the compressor restart beginning another.
+    #[cfg(feature = "stats")]
+    pub fn continuous_run_seconds(&self, service: HvacService) -> Option<u32> {
+        if self.output_service() != Some(service) {
+            return None;
+        };
+        Some(
+            self.last_update?
+                .saturating_sub(self.run_history.running_since(service)?),
+        )
+    }
+
+    /// current circulation phase and the seconds left in it as of the last tick, or `None` without
+    /// circulation or before the first tick
+    ///
+    /// the phase is reported even while a service runs the fan regardless.
+    #[cfg(feature = "fan")]
+    pub fn circulate_phase(&self) -> Option<CirculatePhase> {
+        let (on_seconds, off_seconds) = self.circulate?;
+        let period = on_seconds
+            .checked_add(off_seconds)
+            .filter(|&period| period > 0)?;
+        let position = self.last_update? % period;
+        Some(if position < on_seconds {
+            CirculatePhase::On(on_seconds - position)
+        } else {
+            CirculatePhase::Off(period - position)
+        })
+    }
+
+    /// seconds elapsed at the next boundary where a timer or deadline elapses, after which the
+    /// state may change without any further call, or `None` before the first tick or if nothing
+    /// is pending
+    ///
+    /// this is in the state machine's own seconds, so is scaled by any time scale. a boundary is
+    /// where a change becomes possible, so the state may be unchanged when it arrives.
+    pub fn next_event_seconds(&self) -> Option<u32> {
+        let now = self.last_update?;
+        let grace = self.min_run_grace_seconds;
+        #[cfg(feature = "fan")]
+        let fan_events = [
+            self.start_stagger_until,
+            self.emergency_purge_until,
+            self.fan_purge_until(),
+            self.coil_overrun_until(),
+            #[cfg(feature = "cool")]
+            self.economizer_yield_seconds
+                .zip(self.economizer_cool_since)
+                .map(|(yield_seconds, since)| since.saturating_add(yield_seconds)),
+            self.circulate_phase().map(|phase| match phase {
+                CirculatePhase::On(seconds) | CirculatePhase::Off(seconds) => {
+                    now.saturating_add(seconds)
+                }
+            }),
+        ];
+        #[cfg(not(feature = "fan"))]
+        let fan_events = [];
+        IntoIterator::into_iter([
+            self.armed.map(|(_, at_seconds)| at_seconds),
+            self.startup_wait().map(|wait| now.saturating_add(wait)),
+            #[cfg(feature = "stats")]
+            self.short_cycle_expiry(),
+            #[cfg(feature = "stats")]
+            self.capacity_warning_seconds
+                .zip(self.active_service)
+                .and_then(|(seconds, service)| {
+                    let run_seconds = self.continuous_run_seconds(service)?;
+                    Some(now.saturating_add(seconds.saturating_sub(run_seconds)))
+                }),
+            #[cfg(feature = "setpoint")]
+            self.scheduled_setpoint.map(|setpoint| setpoint.seconds),
+            #[cfg(feature = "cool")]
+            self.last_changeover_seconds
+                .filter(|_| self.auto_hold_seconds > 0)
+                .map(|at| at.saturating_add(self.auto_hold_seconds)),
+            self.heat_wait_seconds
+                .map(|wait| now.saturating_add(wait.saturating_sub(grace))),
+            #[cfg(feature = "cool")]
+            self.cool_wait_seconds
+                .map(|wait| now.saturating_add(wait.saturating_sub(grace))),
+            #[cfg(feature = "cool")]
+            self.valve_settle_wait()
+                .map(|wait| now.saturating_add(wait)),
+            self.heat_called_since
+                .and(self.call_debounce_wait(HvacService::Heat))
+                .map(|wait| now.saturating_add(wait)),
+            #[cfg(feature = "cool")]
+            self.cool_called_since
+                .and(self.call_debounce_wait(HvacService::Cool))
+                .map(|wait| now.saturating_add(wait)),
+        ])
+        .chain(IntoIterator::into_iter(fan_events))
+        .flatten()
+        .chain(
+            self.pending_waits()
+                .map(|(_, wait)| now.saturating_add(wait)),
+        )
+        .filter(|&at| at > now)
+        .min()
+    }
+
+    /// update the state machine by jumping straight to `next_event_seconds`, returning the new
+    /// seconds elapsed and state, or `None` if nothing is pending
+    ///
+    /// repeating this until `None` steps exactly through every boundary rather than polling each
+    /// second. the seconds are the state machine's own, as with `next_event_seconds`.
+    pub fn advance_to_next_event(&mut self) -> Option<(u32, HvacState)> {
+        let at_seconds = self.next_event_seconds()?;
+        Some((at_seconds, self.tick_scaled(at_seconds)))
+    }
+
+    /// seconds left of the fan's minimum run time while it is running, even with a service
+    /// active, or `None` once it has elapsed or while the fan is off
+    ///
+    /// a value that outlasts the service means the fan will linger after it stops. always
+    /// `None` before the first tick.
+    #[cfg(feature = "fan")]
+    pub fn fan_min_run_remaining(&self) -> Option<u32> {
+        if self.fan_active {
+            wait_seconds(
+                self.last_update,
+                self.fan_min_run_seconds,
+                self.fan_last_start_seconds,
+            )
+        } else {
+            None
+        }
+    }
+
+    /// seconds elapsed at which the fan is expected to turn off given the current state and
+    /// timers, or `None` while it is off or running indefinitely
+    ///
+    /// the fan runs indefinitely while manual, requested, economizing, armed, on with an active
+    /// service, or held through a changeover. otherwise this is the latest of the end of its min
+    /// run, start stagger, service purge, cool coil overrun, emergency purge, and circulate on
+    /// phase. a coil overrun may end sooner once the coil warms. always `None` before the first
+    /// tick.
+    #[cfg(feature = "fan")]
+    pub fn fan_off_at(&self) -> Option<u32> {
+        let now = self.last_update?;
+        if !self.fan_active {
+            return None;
+        };
+        if self.emergency_stopped {
+            return Some(
+                self.emergency_purge_until
+                    .map_or(now, |until| until.max(now)),
+            );
+        };
+        if !self.fan_auto
+            || self.fan_requests > 0
+            || self.economizing()
+            || self.armed.is_some()
+            || self.active_service_uses_fan()
+            || self.fan_held_for_changeover()
+        {
+            return None;
+        };
+        let circulate_until = match self.circulate_phase() {
+            Some(CirculatePhase::On(seconds)) => Some(now.saturating_add(seconds)),
+            _ => None,
+        };
+        let until = IntoIterator::into_iter([
+            self.fan_min_run_remaining()
+                .map(|wait| now.saturating_add(wait)),
+            self.start_stagger_until,
+            self.fan_purge_until(),
+            self.coil_overrun_until(),
+            circulate_until,
+        ])
+        .flatten()
+        .fold(now, u32::max);
+        Some(until)
+    }
+
+    /// percentage (0-100) of the trailing `window_seconds` ending at the last tick that `service`
+    /// was active, excluding any time cool was paused by freeze protection
+    ///
+    /// only the 16 most recent service runs are retained, so `None` is returned when the window
+    /// reaches back further than that history, as well as before the first tick or for an empty
+    /// window.
+    #[cfg(feature = "stats")]
+    pub fn duty_cycle(&self, service: HvacService, window_seconds: u32) -> Option<u8> {
+        let to = self.last_update?;
+        if window_seconds == 0 {
+            return None;
+        };
+        let from = to.saturating_sub(window_seconds);
+        let active_seconds = self.run_history.active_seconds(service, from, to)?;
+        Some((u64::from(active_seconds) * 100 / u64::from(window_seconds)) as u8)
+    }
+
+    /// reason heat is being held off despite a call for heat, if any
+    ///
+    /// before the first tick a call is held by `BlockReason::MinRecover`, see `has_ticked`.
+    pub fn heat_block_reason(&self) -> Option<BlockReason> {
+        self.heat_block_reason
+    }
+
+    /// timed gate on `service` with the most time remaining and that time, or `None` if `service`
+    /// is active or no gate has time remaining
+    ///
+    /// gates are timed whether or not `service` is called for, except the call debounce, so this
+    /// shows the most relevant countdown. ties go to the gate declared first in `ConstraintKind`.
+    pub fn binding_constraint(&self, service: HvacService) -> Option<(ConstraintKind, u32)> {
+        if self.active_service == Some(service) {
+            return None;
+        };
+        #[cfg(any(feature = "fan", feature = "cool"))]
+        let now = self.last_update.unwrap_or(0);
+        let (recover, last_stop, called_since) = match service {
+            HvacService::Heat => (
+                self.effective_heat_recover(),
+                self.heat_last_stop_seconds,
+                self.heat_called_since,
+            ),
+            #[cfg(feature = "cool")]
+            HvacService::Cool => (
+                self.effective_cool_recover(),
+                self.cool_last_stop_seconds,
+                self.cool_called_since,
+            ),
+        };
+        #[cfg(feature = "cool")]
+        let opposing_min_run = match self.active_service {
+            Some(HvacService::Heat) => wait_seconds(
+                self.last_update,
+                self.run_min_run(self.heat_min_run_seconds),
+                self.heat_last_start_seconds,
+            ),
+            Some(HvacService::Cool) => wait_seconds(
+                self.last_update,
+                self.run_min_run(self.cool_min_run_seconds),
+                self.cool_last_start_seconds,
+            ),
+            None => None,
+        }
+        .map(|wait| wait.saturating_sub(self.min_run_grace_seconds));
+        #[cfg(feature = "cool")]
+        let auto_hold = self
+            .last_changeover_seconds
+            .filter(|_| self.changeover_held(service))
+            .map(|at| {
+                at.saturating_add(self.auto_hold_seconds)
+                    .saturating_sub(now)
+            });
+        #[cfg(feature = "fan")]
+        let fan_constraints = [
+            (
+                ConstraintKind::FanMinRecover,
+                self.fan_wait_seconds
+                    .filter(|_| self.uses_fan(service) && !self.fan_active),
+            ),
+            (
+                ConstraintKind::StartStagger,
+                self.start_stagger_until
+                    .map(|until| until.saturating_sub(now)),
+            ),
+        ];
+        #[cfg(not(feature = "fan"))]
+        let fan_constraints = [];
+        IntoIterator::into_iter([
+            (
+                ConstraintKind::MinRecover,
+                wait_seconds(self.last_update, recover, last_stop),
+            ),
+            #[cfg(feature = "cool")]
+            (ConstraintKind::OpposingMinRun, opposing_min_run),
+            #[cfg(feature = "cool")]
+            (
+                ConstraintKind::ChangeoverDelay,
+                self.changeover_wait(service),
+            ),
+            #[cfg(feature = "cool")]
+            (ConstraintKind::AutoHold, auto_hold),
+            #[cfg(feature = "cool")]
+            (
+                ConstraintKind::SharedCompressorRecover,
+                self.shared_compressor_wait(),
+            ),
+            (ConstraintKind::StartupDelay, self.startup_wait()),
+            (
+                ConstraintKind::CallDebounce,
+                called_since.and(self.call_debounce_wait(service)),
+            ),
+            #[cfg(feature = "cool")]
+            (
+                ConstraintKind::ValveSettle,
+                self.valve_settle_wait()
+                    .filter(|_| self.valve_service == Some(service)),
+            ),
+        ])
+        .chain(IntoIterator::into_iter(fan_constraints))
+        .filter_map(|(kind, remaining)| Some((kind, remaining.filter(|&seconds| seconds > 0)?)))
+        .fold(
+            None,
+            |binding: Option<(ConstraintKind, u32)>, (kind, remaining)| match binding {
+                Some((_, longest)) if longest >= remaining => binding,
+                _ => Some((kind, remaining)),
+            },
+        )
+    }
+
+    /// reason cool is being held off despite a call for cool, if any
+    ///
+    /// before the first tick a call is held by `BlockReason::MinRecover`, see `has_ticked`.
+    #[cfg(feature = "cool")]
+    pub fn cool_block_reason(&self) -> Option<BlockReason> {
+        self.cool_block_reason
+    }
+
+    /// total seconds a call for heat has spent held off by a wait timer, over the life of this
+    /// state machine
+    ///
+    /// counted between ticks from the block reason at the start of each interval. holds for an
+    /// economizer or humidity lockout are not wait timers and are not counted.
+    #[cfg(feature = "stats")]
+    pub fn heat_blocked_seconds(&self) -> u64 {
+        self.heat_blocked_seconds
+    }
+
+    /// total seconds a call for cool has spent held off by a wait timer, over the life of this
+    /// state machine, see `heat_blocked_seconds`
+    #[cfg(all(feature = "stats", feature = "cool"))]
+    pub fn cool_blocked_seconds(&self) -> u64 {
+        self.cool_blocked_seconds
+    }
+
+    /// update the state machine with new seconds elappsed value
+    ///
+    /// time is expected to be monotonic: a value earlier than the previous tick is treated as no
+    /// time having elapsed. time saturates at `u32::MAX` (about 136 years) rather than wrapping, so
+    /// a clock source that wraps requires a fresh controller once it does.
+    ///
+    /// repeated ticks that do not advance time return the current state without recomputing it
+    /// unless the configuration has changed since.
+    pub fn tick(&mut self, current_seconds: u32) -> HvacState {
+        let current_seconds = self.scaled_seconds(current_seconds);
+        self.tick_scaled(current_seconds)
+    }
+
+    /// update the state machine with the seconds elapsed read from `clock`, exactly as by `tick`
+    pub fn tick_clock<C: Clock + ?Sized>(&mut self, clock: &C) -> HvacState {
+        self.tick(clock.elapsed_seconds())
+    }
+
+    /// update the state machine exactly as by `tick`, also returning the runtime accrued since
+    /// the previous tick, e.g. to feed an external energy meter
+    ///
+    /// the runtime is that of the outputs held over the interval, before this tick's changes
+    /// take effect. the first tick and a tick that does not advance time accrue none.
+    pub fn tick_with_runtime(&mut self, current_seconds: u32) -> (HvacState, RuntimeDelta) {
+        let previous = self.state();
+        let last_update = self.last_update;
+        let state = self.tick(current_seconds);
+        let elapsed = match (last_update, self.last_update) {
+            (Some(last_update), Some(now)) => now.saturating_sub(last_update),
+            _ => 0,
+        };
+        let mut delta = RuntimeDelta::default();
+        match previous.service {
+            Some(HvacService::Heat) => delta.heat_seconds = elapsed,
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => delta.cool_seconds = elapsed,
+            None => {}
+        };
+        #[cfg(feature = "fan")]
+        if previous.fan {
+            delta.fan_seconds = elapsed;
+        };
+        (state, delta)
+    }
+
+    fn tick_scaled(&mut self, current_seconds: u32) -> HvacState {
+        debug_assert!(
+            !self.strict
+                || self
+                    .last_update
+                    .is_none_or(|last_update| current_seconds >= last_update),
+            "strict: tick at {} seconds is earlier than the previous tick at {:?} seconds",
+            current_seconds,
+            self.last_update,
+        );
+        self.last_tick_advanced = self
+            .last_update
+            .is_none_or(|last_update| current_seconds > last_update);
+        let current_seconds = match self.last_update {
+            Some(last_update) if last_update >= current_seconds => {
+                if !self.dirty {
+                    return self.state();
+                };
+                last_update
+            }
+            _ => current_seconds,
+        };
+        if let Some(last_update) = self.last_update {
+            let elapsed = current_seconds - last_update;
+            if elapsed > 0 {
+                self.tick_late = self.tick_interval_seconds.is_some_and(|interval| {
+                    elapsed > interval.saturating_add(interval / TICK_LATE_MARGIN_DIVISOR)
+                });
+            };
+            #[cfg(feature = "stats")]
+            {
+                self.runtime_stats.accumulate(self.state(), elapsed);
+                if self.heat_block_reason.is_some_and(is_wait_timer) {
+                    self.heat_blocked_seconds =
+                        self.heat_blocked_seconds.saturating_add(u64::from(elapsed));
+                };
+                #[cfg(feature = "cool")]
+                if self.cool_block_reason.is_some_and(is_wait_timer) {
+                    self.cool_blocked_seconds =
+                        self.cool_blocked_seconds.saturating_add(u64::from(elapsed));
+                };
+            }
+        };
+        self.last_update = Some(current_seconds);
+        self.first_update = self.first_update.or(self.last_update);
+        let state = self.compute();
+        #[cfg(feature = "fan")]
+        {
+            let target = self.fan_speed_target();
+            self.fan_speed = if self.fan_speed < target {
+                self.fan_speed
+                    .saturating_add(self.fan_ramp_step)
+                    .min(target)
+            } else {
+                self.fan_speed
+                    .saturating_sub(self.fan_ramp_step)
+                    .max(target)
+            };
+        }
+        state
+    }
+
+    /// evaluate the state machine at `at_seconds` elapsed, the general entry point that every
+    /// tick and call is built on
+    ///
+    /// time is handled exactly as by `tick`, but the state is always recomputed, even when time
+    /// has not advanced and nothing has changed since the last evaluation.
+    pub fn evaluate(&mut self, at_seconds: u32) -> HvacState {
+        self.dirty = true;
+        self.tick(at_seconds)
+    }
+
+    /// whether the most recent tick moved time forward, useful to detect a frozen clock
+    ///
+    /// `false` before the first tick.
+    pub fn last_tick_advanced(&self) -> bool {
+        self.last_tick_advanced
+    }
+
+    /// whether the state machine has been ticked at least once
+    ///
+    /// until then no time is known, so every wait is its full configured constraint, as though
+    /// each service had only just stopped, and any call is held off. queries that measure
+    /// elapsed time, such as `duty_cycle`, return `None`.
+    pub fn has_ticked(&self) -> bool {
+        self.last_update.is_some()
+    }
+
+    /// seconds this state machine has observed, from its first tick (or resume) to its last, or
+    /// `None` before the first tick
+    pub fn uptime_seconds(&self) -> Option<u32> {
+        Some(self.last_update?.saturating_sub(self.first_update?))
+    }
+
+    /// update the state machine with new seconds elappsed value, also reporting what the tick did
+    /// or was prevented from doing
+    pub fn checked_tick(&mut self, current_seconds: u32) -> (HvacState, TickOutcome) {
+        let previous = self.state();
+        let state = self.tick(current_seconds);
+        let outcome = match self.held_service {
+            Some(HvacService::Heat) => {
+                TickOutcome::HeldByMinRun(HvacService::Heat, self.heat_wait_seconds.unwrap_or(0))
+            }
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => {
+                TickOutcome::HeldByMinRun(HvacService::Cool, self.cool_wait_seconds.unwrap_or(0))
+            }
+            #[cfg(feature = "fan")]
+            None if self.fan_held_by_min_run() => {
+                TickOutcome::FanHeldByMinRun(self.fan_wait_seconds.unwrap_or(0))
+            }
+            None if state != previous => TickOutcome::Changed,
+            None => TickOutcome::Unchanged,
+        };
+        (state, outcome)
+    }
+
+    /// update the state machine with each `(seconds elapsed, command)` event in order, applying
+    /// the command (if any) and then ticking to its time, returning the final state
+    ///
+    /// out of order timestamps saturate like any other tick: an event earlier than the previous
+    /// one still has its command applied but is treated as no time having elapsed
+    pub fn tick_batch(&mut self, events: &[(u32, Option<HvacCommand>)]) -> HvacState {
+        let mut state = self.state();
+        for &(current_seconds, command) in events {
+            if let Some(command) = command {
+                let _ = self.apply(command);
+            };
+            state = self.tick(current_seconds);
+        }
+        state
+    }
+
+    pub(crate) fn apply(&mut self, command: HvacCommand) -> HvacState {
+        match command {
+            HvacCommand::Tick => self.state(),
+            HvacCommand::Heat => self.heat(),
+            #[cfg(feature = "cool")]
+            HvacCommand::Cool => self.cool(),
+            HvacCommand::Idle => self.idle(),
+            #[cfg(feature = "fan")]
+            HvacCommand::FanAuto(fan_auto) => self.fan_auto(fan_auto),
+        }
+    }
+
+    /// register `hook` to be called whenever any output changes, replacing any previous hook
+    ///
+    /// the hook is a plain function pointer so no heap is needed, which means it cannot capture
+    /// state; it is called synchronously from within the call or tick making the change, so it
+    /// should be short and must reach any state of its own through a static. copies of the state
+    /// machine share the hook, except those made internally by `would_start` and `simulate`.
+    pub fn on_transition(&mut self, hook: fn(HvacTransition)) {
+        self.transition_hook = Some(TransitionHook(hook));
+    }
+
+    #[cfg(feature = "simulate")]
+    pub(crate) fn clear_transition_hook(&mut self) {
+        self.transition_hook = None;
+    }
+
+    /// update state machine with a call for heat, disabling call for cool in the process unless
+    /// the conflict policy keeps both
     pub fn heat(&mut self) -> HvacState {
-        self.heat_calling = true;
-        self.cool_calling = false;
+        self.call(HvacService::Heat);
         self.compute()
     }
 
-    /// update state machine with call for cool, disabling call for heat in the process
+    /// update state machine with call for cool, disabling call for heat in the process unless
+    /// the conflict policy keeps both
+    ///
+    /// ignored on a controller configured with `with_heat_only`.
+    #[cfg(feature = "cool")]
     pub fn cool(&mut self) -> HvacState {
-        self.heat_calling = false;
-        self.cool_calling = true;
+        debug_assert!(
+            !self.strict || !self.heat_only,
+            "strict: cool called on a controller configured heat-only",
+        );
+        self.call(HvacService::Cool);
         self.compute()
     }
 
     /// update state machine setting fan to auto (on with service) or manual (on always)
+    #[cfg(feature = "fan")]
     pub fn fan_auto(&mut self, fan_auto: bool) -> HvacState {
-        self.fan_auto = fan_auto;
+        self.set_fan_auto(fan_auto);
+        self.compute()
+    }
+
+    /// update state machine with a request to run the fan, held until the returned token is
+    /// passed to `release_fan`
+    ///
+    /// requests from any number of subsystems are counted, and the fan runs as though set to
+    /// manual until the last is released, with its usual min run and recover times.
+    #[cfg(feature = "fan")]
+    pub fn request_fan(&mut self) -> FanToken {
+        self.fan_requests = self.fan_requests.saturating_add(1);
+        self.fan_manual_released = false;
+        let _ = self.compute();
+        FanToken(())
+    }
+
+    /// update state machine releasing a request made by `request_fan`, returning the fan to auto
+    /// once no requests remain
+    #[cfg(feature = "fan")]
+    pub fn release_fan(&mut self, token: FanToken) -> HvacState {
+        let FanToken(()) = token;
+        self.fan_requests = self.fan_requests.saturating_sub(1);
+        if self.fan_requests == 0 && self.fan_auto {
+            self.fan_manual_released = true;
+        };
+        self.compute()
+    }
+
+    /// replace every time constraint and the fan mode at once, preserving timing history
+    ///
+    /// the new configuration takes effect on the next tick or call.
+    pub fn reconfigure(&mut self, config: HvacConfig) {
+        self.heat_min_run_seconds = config.heat_min_run_seconds;
+        self.heat_min_recover_seconds = config.heat_min_recover_seconds;
+        #[cfg(feature = "cool")]
+        {
+            self.cool_min_run_seconds = config.cool_min_run_seconds;
+            self.cool_min_recover_seconds = config.cool_min_recover_seconds;
+        }
+        #[cfg(feature = "fan")]
+        {
+            self.fan_min_run_seconds = config.fan_min_run_seconds;
+            self.fan_min_recover_seconds = config.fan_min_recover_seconds;
+            self.fan_manual_off_recover_seconds = config.fan_min_recover_seconds;
+            self.set_fan_auto(config.fan_auto);
+        }
+        self.dirty = true;
+    }
+
+    /// copy of the full state machine, including configuration, calls and timing history, to
+    /// later `restore`, e.g. for undo in a tuning tool
+    pub fn snapshot(&self) -> Hvac {
+        *self
+    }
+
+    /// return the state machine to a `snapshot` taken earlier, replacing all of its state
+    ///
+    /// the snapshot is restored as is, including its transition hook, and takes effect
+    /// immediately without a tick.
+    pub fn restore(&mut self, snapshot: Hvac) {
+        *self = snapshot;
+    }
+
+    /// snapshot of the time constraints and fan mode, as accepted by `reconfigure`
+    ///
+    /// the fan min recover reported is the one applied after an auto stop, which may differ from
+    /// the one after a manual stop as set by `with_fan_recover`.
+    pub fn config(&self) -> HvacConfig {
+        HvacConfig {
+            heat_min_run_seconds: self.heat_min_run_seconds,
+            heat_min_recover_seconds: self.heat_min_recover_seconds,
+            #[cfg(feature = "cool")]
+            cool_min_run_seconds: self.cool_min_run_seconds,
+            #[cfg(feature = "cool")]
+            cool_min_recover_seconds: self.cool_min_recover_seconds,
+            #[cfg(feature = "fan")]
+            fan_min_run_seconds: self.fan_min_run_seconds,
+            #[cfg(feature = "fan")]
+            fan_min_recover_seconds: self.fan_min_recover_seconds,
+            #[cfg(feature = "fan")]
+            fan_auto: self.fan_auto,
+        }
+    }
+
+    /// deterministic 32-bit FNV-1a hash of the persisted configuration and state, e.g. to detect
+    /// a corrupted copy restored from flash
+    ///
+    /// covered are the settings applied by all `with_*` builders, calls, overrides, outputs,
+    /// timers, and history. the transition hook and the bookkeeping recomputed by every input or
+    /// tick are not: the cached waits, block reasons, and held service, the economizing, short
+    /// cycling, and capacity warning flags, and the late tick and dirty markers. fields are hashed
+    /// in declaration order with fixed little-endian encodings, so the result is the same on
+    /// every platform, and fields compiled out by a disabled feature are skipped.
+    ///
+    /// the checksum is stable across crate versions within a major version for the same
+    /// features: the order and encoding of existing fields never change, and a field added later
+    /// is appended to the end of the order and hashed only while it differs from its default.
+    pub fn checksum(&self) -> u32 {
+        let mut hash = Fnv1a::new();
+        hash.service(self.active_service);
+        #[cfg(feature = "fan")]
+        hash.bool(self.fan_active);
+        hash.option_u32(self.last_update);
+        hash.option_u32(self.first_update);
+        hash.bool(self.heat_calling);
+        hash.option_u32(self.heat_min_run_seconds);
+        hash.option_u32(self.heat_min_recover_seconds);
+        hash.option_u32(self.heat_last_start_seconds);
+        hash.option_u32(self.heat_last_stop_seconds);
+        hash.u16(self.heat_recover_ratio_percent);
+        #[cfg(feature = "cool")]
+        {
+            hash.bool(self.cool_calling);
+            hash.option_u32(self.cool_min_run_seconds);
+            hash.option_u32(self.cool_min_recover_seconds);
+            hash.option_u32(self.cool_post_run_off_seconds);
+            hash.bool(self.cool_last_run_completed);
+            hash.option_u32(self.cool_last_start_seconds);
+            hash.option_u32(self.cool_last_stop_seconds);
+            hash.u16(self.cool_recover_ratio_percent);
+            hash.option(self.cool_humidity_lockout_rh, Fnv1a::u8);
+            hash.option(self.humidity_rh, Fnv1a::u8);
+        }
+        #[cfg(feature = "fan")]
+        {
+            hash.bool(self.fan_auto);
+            hash.option_u32(self.fan_min_run_seconds);
+            hash.option_u32(self.fan_min_recover_seconds);
+            hash.option_u32(self.fan_manual_off_recover_seconds);
+            hash.bool(self.fan_manual_released);
+            hash.u16(self.fan_requests);
+            hash.option(self.circulate, |hash, (on_seconds, off_seconds)| {
+                hash.u32(on_seconds);
+                hash.u32(off_seconds);
+            });
+            hash.bool(self.fan_last_stop_manual);
+            hash.option_u32(self.fan_last_start_seconds);
+            hash.option_u32(self.fan_last_stop_seconds);
+        }
+        #[cfg(feature = "erv")]
+        {
+            hash.bool(self.erv_requested);
+            hash.bool(self.erv_active);
+            hash.option_u32(self.erv_min_run_seconds);
+            hash.option_u32(self.erv_min_recover_seconds);
+            hash.option_u32(self.erv_last_start_seconds);
+            hash.option_u32(self.erv_last_stop_seconds);
+        }
+        #[cfg(feature = "setpoint")]
+        {
+            self.temperature_history.hash(&mut hash);
+            hash.option(self.scheduled_setpoint, |hash, setpoint| {
+                hash.u32(setpoint.seconds);
+                hash.i16(setpoint.temperature);
+            });
+            hash.service(self.recovery_service);
+            hash.bool(self.occupied);
+            hash.i16(self.unoccupied_deadband);
+            hash.u8(self.temp_filter_alpha_percent);
+            hash.option(self.filtered_temperature, Fnv1a::i16);
+        }
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        {
+            hash.bool(self.economize);
+            hash.option_u32(self.economizer_yield_seconds);
+            hash.option_u32(self.economizer_cool_since);
+        }
+        #[cfg(feature = "fan")]
+        {
+            hash.u32(self.start_stagger_seconds);
+            hash.option_u32(self.start_stagger_until);
+        }
+        #[cfg(feature = "cool")]
+        hash.u32(self.auto_hold_seconds);
+        hash.u32(self.min_run_grace_seconds);
+        #[cfg(feature = "cool")]
+        {
+            hash.option_u32(self.heat_to_cool_delay_seconds);
+            hash.option_u32(self.cool_to_heat_delay_seconds);
+        }
+        hash.service(self.last_service);
+        #[cfg(feature = "cool")]
+        {
+            hash.service(self.valve_service);
+            hash.u32(self.valve_settle_seconds);
+            hash.option_u32(self.valve_changed_seconds);
+            hash.option_u32(self.last_changeover_seconds);
+        }
+        #[cfg(feature = "fan")]
+        hash.fan_config(self.heat_fan_config);
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        {
+            hash.fan_config(self.cool_fan_config);
+            hash.bool(self.fan_continuous_on_changeover);
+            hash.option(self.cool_coil_overrun, |hash, (stop_above, max_seconds)| {
+                hash.i16(stop_above);
+                hash.u32(max_seconds);
+            });
+            hash.option(self.coil_temp, Fnv1a::i16);
+            hash.option(
+                self.freeze_protection,
+                |hash, (pause_below, resume_above)| {
+                    hash.i16(pause_below);
+                    hash.i16(resume_above);
+                },
+            );
+            hash.bool(self.freeze_protecting);
+        }
+        hash.bool(self.emergency_stopped);
+        hash.bool(self.powered);
+        #[cfg(feature = "stats")]
+        hash.option(
+            self.short_cycle_warning,
+            |hash, (max_starts, window_seconds)| {
+                hash.u8(max_starts);
+                hash.u32(window_seconds);
+            },
+        );
+        hash.option_u32(self.heat_min_run_override);
+        #[cfg(feature = "cool")]
+        hash.option_u32(self.cool_min_run_override);
+        hash.option_u32(self.run_min_run_override);
+        #[cfg(feature = "stats")]
+        hash.option_u32(self.capacity_warning_seconds);
+        #[cfg(feature = "cool")]
+        hash.option_u32(self.shared_compressor_recover_seconds);
+        hash.option_u32(self.tick_interval_seconds);
+        hash.u32(self.startup_delay_seconds);
+        hash.u32(self.call_debounce_seconds);
+        hash.u32(self.heat_min_call_seconds);
+        #[cfg(feature = "cool")]
+        hash.u32(self.cool_min_call_seconds);
+        hash.option_u32(self.heat_called_since);
+        #[cfg(feature = "cool")]
+        hash.option_u32(self.cool_called_since);
+        hash.bool(self.strict);
+        #[cfg(feature = "fan")]
+        {
+            hash.u32(self.emergency_fan_overrun_seconds);
+            hash.option_u32(self.emergency_purge_until);
+        }
+        #[cfg(feature = "cool")]
+        hash.conflict_policy(self.conflict_policy);
+        #[cfg(feature = "fan")]
+        {
+            hash.u8(self.fan_ramp_step);
+            hash.u8(self.fan_speed);
+        }
+        hash.u32(self.time_scale_numerator);
+        hash.u32(self.time_scale_denominator);
+        hash.option(self.armed, |hash, (service, at_seconds)| {
+            hash.service(Some(service));
+            hash.u32(at_seconds);
+        });
+        hash.bool(self.heat_inverted);
+        #[cfg(feature = "cool")]
+        hash.bool(self.cool_inverted);
+        #[cfg(feature = "fan")]
+        hash.bool(self.fan_inverted);
+        #[cfg(feature = "stats")]
+        {
+            self.run_history.hash(&mut hash);
+            self.runtime_stats.hash(&mut hash);
+            hash.u64(self.heat_blocked_seconds);
+            #[cfg(feature = "cool")]
+            hash.u64(self.cool_blocked_seconds);
+        }
+        hash.bool(self.testing);
+        hash.bool(self.auto_zero_on_first_call);
+        #[cfg(all(feature = "fan", feature = "cool"))]
+        {
+            hash.option(
+                self.humidity_fan_modulation,
+                |hash, (high_rh_speed, low_rh_speed, threshold_rh_percent)| {
+                    hash.u8(high_rh_speed);
+                    hash.u8(low_rh_speed);
+                    hash.u8(threshold_rh_percent);
+                },
+            );
+        }
+        #[cfg(feature = "cool")]
+        hash.bool(self.heat_only);
+        hash.finish()
+    }
+
+    /// update state machine with a newly observed temperature
+    ///
+    /// temperature is in whatever units the caller chooses (e.g., tenths of a degree) and is
+    /// recorded against the most recent tick for use by scheduled setpoint recovery, after any
+    /// smoothing by the temperature filter
+    #[cfg(feature = "setpoint")]
+    pub fn set_temperature(&mut self, temperature: i16) -> HvacState {
+        let temperature = match self.filtered_temperature {
+            Some(filtered) => {
+                let delta = i32::from(temperature) - i32::from(filtered);
+                let step = delta * i32::from(self.temp_filter_alpha_percent);
+                // round half away from zero so small steps still move the smoothed value
+                let step = (step + step.signum() * 50) / 100;
+                (i32::from(filtered) + step) as i16
+            }
+            None => temperature,
+        };
+        self.filtered_temperature = Some(temperature);
+        self.temperature_history.record(
+            self.last_update.unwrap_or(0),
+            temperature,
+            self.active_service,
+        );
+        self.compute()
+    }
+
+    /// update state machine with a setpoint to reach by `target_time` seconds elapsed
+    ///
+    /// heat or cool is called for early enough to reach `target_temp` on time based on the rate
+    /// of change observed from previous temperature updates while that service was active.
+    /// recovery calls stop once the target temperature is reached or `target_time` arrives, and
+    /// any explicit call for the opposing service takes precedence.
+    #[cfg(feature = "setpoint")]
+    pub fn schedule_setpoint(&mut self, target_time: u32, target_temp: i16) -> HvacState {
+        self.testing = false;
+        self.scheduled_setpoint = Some(ScheduledSetpoint {
+            seconds: self.scaled_seconds(target_time),
+            temperature: target_temp,
+        });
+        self.recovery_service = None;
+        self.compute()
+    }
+
+    /// update state machine disabling any calls for service and running the fan manually
+    ///
+    /// this is equivalent to `idle` followed by `fan_auto(false)`, and is exited by returning the
+    /// fan to auto with `fan_auto(true)`
+    #[cfg(feature = "fan")]
+    pub fn fan_only(&mut self) -> HvacState {
+        self.heat_calling = false;
+        #[cfg(feature = "cool")]
+        {
+            self.cool_calling = false;
+        }
+        self.testing = false;
+        self.armed = None;
+        self.set_fan_auto(false);
+        self.compute()
+    }
+
+    /// update state machine with whether free cooling from outside air is available
+    ///
+    /// while economizing the fan runs and calls for cool are served without the compressor. a
+    /// call for cool that persists beyond the economizer yield time is assumed to be more than
+    /// free cooling can keep up with and starts the compressor as usual. a call for heat always
+    /// ends economizing.
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub fn economize(&mut self, enable: bool) -> HvacState {
+        self.economize = enable;
+        self.compute()
+    }
+
+    /// update state machine with a newly measured cooling coil temperature, used by the cool coil
+    /// overrun
+    #[cfg(all(feature = "fan", feature = "cool"))]
+    pub fn set_coil_temp(&mut self, temp: i16) -> HvacState {
+        self.coil_temp = Some(temp);
+        self.compute()
+    }
+
+    /// update state machine with a newly measured relative humidity, in percent
+    #[cfg(feature = "cool")]
+    pub fn set_humidity(&mut self, rh_percent: u8) -> HvacState {
+        self.humidity_rh = Some(rh_percent);
+        self.compute()
+    }
+
+    /// update state machine with whether the building is occupied
+    ///
+    /// while unoccupied, the unoccupied deadband is added before the temperature-driven
+    /// scheduled setpoint recovery calls for service. explicit calls for service are unaffected.
+    #[cfg(feature = "setpoint")]
+    pub fn set_occupancy(&mut self, occupied: bool) -> HvacState {
+        self.occupied = occupied;
+        self.compute()
+    }
+
+    /// update state machine turning the energy recovery ventilator on or off, independent of the
+    /// fan and any service
+    #[cfg(feature = "erv")]
+    pub fn erv(&mut self, on: bool) -> HvacState {
+        self.erv_requested = on;
+        self.compute()
+    }
+
+    /// update state machine with a call for `service` that will be made in `in_seconds`, starting
+    /// the fan ahead of it so it is already spun up when the service starts
+    ///
+    /// the fan starts no earlier than the service's start stagger or pre-purge, plus any
+    /// remaining fan recover time, before the call is made. the armed call is cleared by `idle`.
+    /// for a service not coupled to the fan, or without the `fan` feature, the call is only
+    /// deferred.
+    pub fn arm_service(&mut self, service: HvacService, in_seconds: u32) -> HvacState {
+        self.testing = false;
+        #[cfg(feature = "cool")]
+        if self.ignores(service) {
+            return self.compute();
+        };
+        self.armed = Some((
+            service,
+            self.last_update.unwrap_or(0).saturating_add(in_seconds),
+        ));
+        self.compute()
+    }
+
+    /// update state machine to energize exactly the requested outputs for commissioning, also
+    /// reporting why a requested output is held off by a safety timer, if it is
+    ///
+    /// any calls, armed call, and scheduled setpoint recovery are replaced by the requested
+    /// outputs, which persist and start once their timers allow. only the run, recover,
+    /// changeover, and fan timers are honored so a compressor is never rapid cycled, while the
+    /// economizer, humidity lockout, call debounce and min call duration, and conflict policy are
+    /// bypassed until the next call, `idle`, or other input replacing the calls. the fan is left
+    /// running manually until returned to auto with `fan_auto(true)`.
+    pub fn test_output(
+        &mut self,
+        service: Option<HvacService>,
+        #[cfg(feature = "fan")] fan: bool,
+    ) -> (HvacState, Option<BlockReason>) {
+        self.heat_calling = service == Some(HvacService::Heat);
+        #[cfg(feature = "cool")]
+        {
+            self.cool_calling = service == Some(HvacService::Cool) && !self.heat_only;
+        }
+        self.armed = None;
+        #[cfg(feature = "setpoint")]
+        {
+            self.scheduled_setpoint = None;
+            self.recovery_service = None;
+        }
+        self.testing = true;
+        #[cfg(feature = "fan")]
+        self.set_fan_auto(!fan);
+        let state = self.compute();
+        let blocked = match service {
+            Some(HvacService::Heat) => self.heat_block_reason,
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => self.cool_block_reason,
+            None => None,
+        };
+        #[cfg(feature = "fan")]
+        let blocked = blocked.or(if fan && !state.fan {
+            Some(BlockReason::FanMinRecover)
+        } else {
+            None
+        });
+        (state, blocked)
+    }
+
+    /// update state machine shutting down heat, cool, and the energy recovery ventilator
+    /// immediately, regardless of their min run times, and latching them off until
+    /// `clear_emergency_stop`
+    ///
+    /// any calls for service are cleared, and calls made while latched are held off by
+    /// `BlockReason::EmergencyStop`. with `purge` a running fan continues for the emergency fan
+    /// overrun before stopping, which suits shutdowns where clearing combustion byproducts helps,
+    /// such as a flame rollout or high limit trip. without it the fan stops at once too, the true
+    /// all-off needed where moving air would spread the hazard, such as a refrigerant leak or
+    /// smoke detection.
+    pub fn emergency_stop(&mut self, #[cfg(feature = "fan")] purge: bool) -> HvacState {
+        self.heat_calling = false;
+        #[cfg(feature = "cool")]
+        {
+            self.cool_calling = false;
+        }
+        self.testing = false;
+        self.armed = None;
+        #[cfg(feature = "erv")]
+        {
+            self.erv_requested = false;
+        }
+        self.emergency_stopped = true;
+        #[cfg(feature = "fan")]
+        {
+            self.emergency_purge_until = if purge && self.fan_active {
+                Some(
+                    self.last_update
+                        .unwrap_or(0)
+                        .saturating_add(self.emergency_fan_overrun_seconds),
+                )
+            } else {
+                None
+            };
+        }
+        self.compute()
+    }
+
+    /// update state machine releasing the latch set by `emergency_stop`, after which calls are
+    /// served subject to the usual recover times
+    pub fn clear_emergency_stop(&mut self) -> HvacState {
+        self.emergency_stopped = false;
+        #[cfg(feature = "fan")]
+        {
+            self.emergency_purge_until = None;
+        }
+        self.compute()
+    }
+
+    /// if latched off by `emergency_stop`
+    pub fn emergency_stopped(&self) -> bool {
+        self.emergency_stopped
+    }
+
+    /// update state machine powering the system off, as with a thermostat set to off
+    ///
+    /// unlike `idle`, nothing runs until `power_on`: any calls for service are cleared and
+    /// further calls held off by `BlockReason::PoweredOff`, and the energy recovery ventilator and
+    /// any manual fan stop too. outputs still wind down respecting their min run times, unlike
+    /// `emergency_stop`.
+    pub fn power_off(&mut self) -> HvacState {
+        self.heat_calling = false;
+        #[cfg(feature = "cool")]
+        {
+            self.cool_calling = false;
+        }
+        self.testing = false;
+        self.armed = None;
+        #[cfg(feature = "erv")]
+        {
+            self.erv_requested = false;
+        }
+        self.powered = false;
+        self.compute()
+    }
+
+    /// update state machine powering the system back on after `power_off`
+    ///
+    /// calls made while powered off are discarded, so the system resumes idle with the fan as
+    /// configured.
+    pub fn power_on(&mut self) -> HvacState {
+        if !self.powered {
+            self.heat_calling = false;
+            #[cfg(feature = "cool")]
+            {
+                self.cool_calling = false;
+            }
+            self.testing = false;
+            self.armed = None;
+            #[cfg(feature = "erv")]
+            {
+                self.erv_requested = false;
+            }
+            self.powered = true;
+        };
         self.compute()
     }
 
+    /// use `seconds` as the heat min run for the next heat run only, e.g. a longer run after a
+    /// defrost, after which the configured min run applies again
+    ///
+    /// the override is consumed when heat next starts, and replaces any earlier override not yet
+    /// consumed.
+    pub fn override_next_heat_min_run(&mut self, seconds: u32) {
+        self.heat_min_run_override = Some(seconds);
+    }
+
+    /// use `seconds` as the cool min run for the next cool run only, see
+    /// `override_next_heat_min_run`
+    #[cfg(feature = "cool")]
+    pub fn override_next_cool_min_run(&mut self, seconds: u32) {
+        self.cool_min_run_override = Some(seconds);
+    }
+
     /// update state machine disabling any calls for service
+    ///
+    /// this also cancels any armed call, releasing the fan it holds on. see `clear_service_calls`
+    /// to clear only the calls.
     pub fn idle(&mut self) -> HvacState {
         self.heat_calling = false;
-        self.cool_calling = false;
+        #[cfg(feature = "cool")]
+        {
+            self.cool_calling = false;
+        }
+        self.testing = false;
+        self.armed = None;
+        self.compute()
+    }
+
+    /// update state machine disabling the calls for heat and cool and nothing else
+    ///
+    /// unlike `idle`, an armed call is kept, along with the fan it holds on. neither clears the
+    /// fan mode, fan requests, or circulation, so a manual fan is left exactly as is. the state is
+    /// still recomputed so that services stop once their min run allows.
+    pub fn clear_service_calls(&mut self) -> HvacState {
+        self.heat_calling = false;
+        #[cfg(feature = "cool")]
+        {
+            self.cool_calling = false;
+        }
+        self.testing = false;
         self.compute()
     }
 }
 
+/// interface to an hvac controller, allowing callers to substitute their own implementation
+pub trait HvacController {
+    /// update the controller with new seconds elapsed value
+    fn tick(&mut self, current_seconds: u32) -> HvacState;
+    /// update the controller with a call for heat, disabling call for cool in the process
+    fn heat(&mut self) -> HvacState;
+    /// update the controller with call for cool, disabling call for heat in the process
+    #[cfg(feature = "cool")]
+    fn cool(&mut self) -> HvacState;
+    /// update the controller setting fan to auto (on with service) or manual (on always)
+    #[cfg(feature = "fan")]
+    fn fan_auto(&mut self, fan_auto: bool) -> HvacState;
+    /// update the controller disabling any calls for service
+    fn idle(&mut self) -> HvacState;
+}
+
+impl HvacController for Hvac {
+    fn tick(&mut self, current_seconds: u32) -> HvacState {
+        Hvac::tick(self, current_seconds)
+    }
+
+    fn heat(&mut self) -> HvacState {
+        Hvac::heat(self)
+    }
+
+    #[cfg(feature = "cool")]
+    fn cool(&mut self) -> HvacState {
+        Hvac::cool(self)
+    }
+
+    #[cfg(feature = "fan")]
+    fn fan_auto(&mut self, fan_auto: bool) -> HvacState {
+        Hvac::fan_auto(self, fan_auto)
+    }
+
+    fn idle(&mut self) -> HvacState {
+        Hvac::idle(self)
+    }
+}
+
+/// monotonic time source read by `Hvac::tick_clock`, e.g. a wrapper around a hal timer
+pub trait Clock {
+    /// seconds elapsed since some fixed point, never decreasing
+    fn elapsed_seconds(&self) -> u32;
+}
+
+impl Clock for u32 {
+    fn elapsed_seconds(&self) -> u32 {
+        *self
+    }
+}
+
 /// convienence module that re-exports the typical api
 pub mod prelude {
+    #[cfg(feature = "stats")]
+    #[doc(no_inline)]
+    pub use crate::RuntimeStats;
+    #[cfg(feature = "simulate")]
+    #[doc(no_inline)]
+    pub use crate::{simulate, SimStats};
+    #[doc(no_inline)]
+    pub use crate::{
+        BlockReason, Clock, ConstraintKind, Hvac, HvacCommand, HvacConfig, HvacConfigError,
+        HvacController, HvacService, HvacState, HvacTransition, LogicalState, OperatingMode,
+        RawOutputs, RuntimeDelta, StateDiff, StateTransition, TickOutcome, TimerKind,
+    };
+    #[cfg(feature = "fan")]
+    #[doc(no_inline)]
+    pub use crate::{CirculatePhase, FanToken, ServiceFanConfig};
+    #[cfg(feature = "cool")]
+    #[doc(no_inline)]
+    pub use crate::{ConflictPolicy, ReversingValve};
+    #[cfg(feature = "zone")]
     #[doc(no_inline)]
-    pub use crate::{Hvac, HvacService, HvacState};
+    pub use crate::{ZoneAggregator, ZoneStrategy};
 }
@@ -0,0 +1,123 @@
+use crate::checksum::Fnv1a;
+use crate::HvacService;
+use core::cmp::Ordering;
+
+const HISTORY_LEN: usize = 8;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct TemperatureSample {
+    seconds: u32,
+    temperature: i16,
+    service: Option<HvacService>,
+}
+
+/// setpoint the space should reach by a scheduled time
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) struct ScheduledSetpoint {
+    pub(crate) seconds: u32,
+    pub(crate) temperature: i16,
+}
+
+/// fixed-size ring buffer of recent temperature samples
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) struct TemperatureHistory {
+    samples: [Option<TemperatureSample>; HISTORY_LEN],
+    next: usize,
+}
+
+impl TemperatureHistory {
+    /// record a temperature observed at `seconds` while `service` was active
+    pub(crate) fn record(&mut self, seconds: u32, temperature: i16, service: Option<HvacService>) {
+        self.samples[self.next] = Some(TemperatureSample {
+            seconds,
+            temperature,
+            service,
+        });
+        self.next = (self.next + 1) % HISTORY_LEN;
+    }
+
+    /// feed every sample slot in storage order, then the write position
+    pub(crate) fn hash(&self, hash: &mut Fnv1a) {
+        for &sample in self.samples.iter() {
+            hash.option(sample, |hash, sample| {
+                hash.u32(sample.seconds);
+                hash.i16(sample.temperature);
+                hash.service(sample.service);
+            });
+        }
+        hash.usize(self.next);
+    }
+
+    /// most recently recorded temperature, if any
+    pub(crate) fn latest(&self) -> Option<i16> {
+        self.samples[(self.next + HISTORY_LEN - 1) % HISTORY_LEN].map(|sample| sample.temperature)
+    }
+
+    /// estimated seconds for `service` to move the temperature by `delta`, based on consecutive
+    /// samples recorded while it was active
+    fn seconds_to_change(&self, service: HvacService, delta: u32) -> Option<u32> {
+        let mut seconds = 0u64;
+        let mut change = 0i64;
+        let mut previous: Option<TemperatureSample> = None;
+        for i in 0..HISTORY_LEN {
+            let sample = self.samples[(self.next + i) % HISTORY_LEN];
+            if let (Some(a), Some(b)) = (previous, sample) {
+                if a.service == Some(service) && b.service == Some(service) {
+                    seconds += u64::from(b.seconds.saturating_sub(a.seconds));
+                    change += i64::from(b.temperature) - i64::from(a.temperature);
+                };
+            };
+            previous = sample;
+        }
+        let change = match service {
+            HvacService::Heat => change,
+            #[cfg(feature = "cool")]
+            HvacService::Cool => -change,
+        };
+        if change > 0 && seconds > 0 {
+            let estimate = u64::from(delta) * seconds / change as u64;
+            Some(if estimate > u64::from(u32::MAX) {
+                u32::MAX
+            } else {
+                estimate as u32
+            })
+        } else {
+            None
+        }
+    }
+
+    /// service that should be called to reach `setpoint` on time at `now`, if any
+    ///
+    /// recovery does not begin while the temperature is within `deadband` of the setpoint.
+    /// without an observed rate of change recovery begins immediately, and once begun it
+    /// continues until the setpoint temperature is reached
+    pub(crate) fn recovery_service(
+        &self,
+        setpoint: ScheduledSetpoint,
+        now: u32,
+        recovering: Option<HvacService>,
+        deadband: u32,
+    ) -> Option<HvacService> {
+        let temperature = self.latest()?;
+        let delta = i32::from(setpoint.temperature) - i32::from(temperature);
+        let service = match delta.cmp(&0) {
+            Ordering::Greater => HvacService::Heat,
+            #[cfg(feature = "cool")]
+            Ordering::Less => HvacService::Cool,
+            _ => return None,
+        };
+        if recovering == Some(service) {
+            return recovering;
+        };
+        if delta.unsigned_abs() <= deadband {
+            return None;
+        };
+        match self.seconds_to_change(service, delta.unsigned_abs()) {
+            Some(seconds) if now.saturating_add(seconds) < setpoint.seconds => None,
+            _ => Some(service),
+        }
+    }
+}
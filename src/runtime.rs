@@ -0,0 +1,64 @@
+use crate::checksum::Fnv1a;
+use crate::{HvacService, HvacState};
+
+/// runtime and cycle counters accumulated since they were last taken
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RuntimeStats {
+    /// total seconds heat was active
+    pub heat_runtime_seconds: u64,
+    /// total seconds cool was active
+    #[cfg(feature = "cool")]
+    pub cool_runtime_seconds: u64,
+    /// total seconds the fan was active
+    #[cfg(feature = "fan")]
+    pub fan_runtime_seconds: u64,
+    /// number of times heat started
+    pub heat_cycles: u64,
+    /// number of times cool started
+    #[cfg(feature = "cool")]
+    pub cool_cycles: u64,
+}
+
+impl RuntimeStats {
+    /// record `state` having been held for `seconds`
+    pub(crate) fn accumulate(&mut self, state: HvacState, seconds: u32) {
+        match state.service {
+            Some(HvacService::Heat) => {
+                self.heat_runtime_seconds =
+                    self.heat_runtime_seconds.saturating_add(u64::from(seconds))
+            }
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => {
+                self.cool_runtime_seconds =
+                    self.cool_runtime_seconds.saturating_add(u64::from(seconds))
+            }
+            None => {}
+        };
+        #[cfg(feature = "fan")]
+        if state.fan {
+            self.fan_runtime_seconds = self.fan_runtime_seconds.saturating_add(u64::from(seconds));
+        };
+    }
+
+    /// feed every counter in declaration order
+    pub(crate) fn hash(&self, hash: &mut Fnv1a) {
+        hash.u64(self.heat_runtime_seconds);
+        #[cfg(feature = "cool")]
+        hash.u64(self.cool_runtime_seconds);
+        #[cfg(feature = "fan")]
+        hash.u64(self.fan_runtime_seconds);
+        hash.u64(self.heat_cycles);
+        #[cfg(feature = "cool")]
+        hash.u64(self.cool_cycles);
+    }
+
+    /// record `service` starting
+    pub(crate) fn start(&mut self, service: HvacService) {
+        match service {
+            HvacService::Heat => self.heat_cycles = self.heat_cycles.saturating_add(1),
+            #[cfg(feature = "cool")]
+            HvacService::Cool => self.cool_cycles = self.cool_cycles.saturating_add(1),
+        };
+    }
+}
@@ -0,0 +1,133 @@
+#[cfg(feature = "simulate")]
+use crate::{Hvac, HvacService, HvacState};
+
+/// command replayed against a controller by `simulate`
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HvacCommand {
+    /// tick only
+    Tick,
+    /// call for heat
+    Heat,
+    /// call for cool
+    #[cfg(feature = "cool")]
+    Cool,
+    /// disable any calls for service
+    Idle,
+    /// set fan to auto (on with service) or manual (on always)
+    #[cfg(feature = "fan")]
+    FanAuto(bool),
+}
+
+/// aggregate statistics of a simulated trajectory
+#[cfg(feature = "simulate")]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SimStats {
+    /// total seconds heat was active
+    pub heat_runtime_seconds: u64,
+    /// total seconds cool was active
+    #[cfg(feature = "cool")]
+    pub cool_runtime_seconds: u64,
+    /// total seconds the fan was active
+    #[cfg(feature = "fan")]
+    pub fan_runtime_seconds: u64,
+    /// number of times heat started
+    pub heat_cycles: u64,
+    /// number of times cool started
+    #[cfg(feature = "cool")]
+    pub cool_cycles: u64,
+    /// longest continuous run of any service in seconds
+    pub max_continuous_run_seconds: u32,
+    /// total seconds a called-for service was held off
+    pub blocked_seconds: u64,
+}
+
+#[cfg(feature = "simulate")]
+#[derive(Clone, Copy, Debug)]
+struct Run {
+    service: Option<HvacService>,
+    start: u32,
+}
+
+#[cfg(feature = "simulate")]
+impl SimStats {
+    fn observe(&mut self, run: &mut Run, state: HvacState, seconds: u32) {
+        if state.service != run.service {
+            self.end_run(*run, seconds);
+            match state.service {
+                Some(HvacService::Heat) => self.heat_cycles = self.heat_cycles.saturating_add(1),
+                #[cfg(feature = "cool")]
+                Some(HvacService::Cool) => self.cool_cycles = self.cool_cycles.saturating_add(1),
+                None => {}
+            };
+            *run = Run {
+                service: state.service,
+                start: seconds,
+            };
+        };
+    }
+
+    fn end_run(&mut self, run: Run, seconds: u32) {
+        if run.service.is_some() {
+            self.max_continuous_run_seconds = self
+                .max_continuous_run_seconds
+                .max(seconds.saturating_sub(run.start));
+        };
+    }
+
+    fn accumulate(&mut self, state: HvacState, blocked: bool, seconds: u32) {
+        match state.service {
+            Some(HvacService::Heat) => {
+                self.heat_runtime_seconds =
+                    self.heat_runtime_seconds.saturating_add(u64::from(seconds))
+            }
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => {
+                self.cool_runtime_seconds =
+                    self.cool_runtime_seconds.saturating_add(u64::from(seconds))
+            }
+            None => {}
+        };
+        #[cfg(feature = "fan")]
+        if state.fan {
+            self.fan_runtime_seconds = self.fan_runtime_seconds.saturating_add(u64::from(seconds));
+        };
+        if blocked {
+            self.blocked_seconds = self.blocked_seconds.saturating_add(u64::from(seconds));
+        };
+    }
+}
+
+/// replay time-ordered `events` against a copy of `config`, ticking to each event's time before
+/// applying its command, and return statistics of the resulting trajectory up to the last event
+#[cfg(feature = "simulate")]
+pub fn simulate(config: &Hvac, events: &[(u32, HvacCommand)]) -> SimStats {
+    let mut hvac = *config;
+    hvac.clear_transition_hook();
+    let mut stats = SimStats::default();
+    let mut run = Run {
+        service: hvac.state().service,
+        start: 0,
+    };
+    let mut previous: Option<(u32, HvacState, bool)> = None;
+    for &(seconds, command) in events {
+        if let Some((last_seconds, state, blocked)) = previous {
+            stats.accumulate(state, blocked, seconds.saturating_sub(last_seconds));
+        } else {
+            run.start = seconds;
+        };
+        let state = hvac.tick(seconds);
+        stats.observe(&mut run, state, seconds);
+        let state = hvac.apply(command);
+        stats.observe(&mut run, state, seconds);
+        let blocked = hvac.heat_block_reason().is_some();
+        #[cfg(feature = "cool")]
+        let blocked = blocked || hvac.cool_block_reason().is_some();
+        previous = Some((seconds, state, blocked));
+    }
+    if let Some((last_seconds, _, _)) = previous {
+        stats.end_run(run, last_seconds);
+    };
+    stats
+}
@@ -0,0 +1,82 @@
+use crate::{Hvac, HvacService, HvacState};
+
+/// strategy used to combine zone demands into a single system call
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ZoneStrategy {
+    /// any zone calling for a service results in a system call for it
+    AnyCall,
+    /// more than half of all zones must call for a service to result in a system call for it
+    Majority,
+}
+
+/// combines the heat and cool demands of multiple zones sharing one air handler
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ZoneAggregator {
+    strategy: ZoneStrategy,
+}
+
+impl Default for ZoneAggregator {
+    fn default() -> Self {
+        Self {
+            strategy: ZoneStrategy::AnyCall,
+        }
+    }
+}
+
+impl ZoneAggregator {
+    /// use a custom strategy for combining zone demands
+    pub fn with_strategy(mut self, strategy: ZoneStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// combine zone demands into a single system call, if any
+    ///
+    /// when both services qualify, the one called for by more zones wins with ties favoring heat
+    pub fn aggregate<I>(&self, demands: I) -> Option<HvacService>
+    where
+        I: IntoIterator<Item = Option<HvacService>>,
+    {
+        let mut zones = 0u32;
+        let mut heat = 0u32;
+        #[cfg(feature = "cool")]
+        let mut cool = 0u32;
+        for demand in demands {
+            zones = zones.saturating_add(1);
+            match demand {
+                Some(HvacService::Heat) => heat = heat.saturating_add(1),
+                #[cfg(feature = "cool")]
+                Some(HvacService::Cool) => cool = cool.saturating_add(1),
+                None => {}
+            };
+        }
+        let qualifies = |calls: u32| match self.strategy {
+            ZoneStrategy::AnyCall => calls > 0,
+            ZoneStrategy::Majority => calls > zones / 2,
+        };
+        #[cfg(feature = "cool")]
+        if qualifies(cool) && cool > heat {
+            return Some(HvacService::Cool);
+        };
+        if qualifies(heat) {
+            Some(HvacService::Heat)
+        } else {
+            None
+        }
+    }
+
+    /// combine zone demands and update the state machine with the resulting call
+    pub fn apply<I>(&self, hvac: &mut Hvac, demands: I) -> HvacState
+    where
+        I: IntoIterator<Item = Option<HvacService>>,
+    {
+        match self.aggregate(demands) {
+            Some(HvacService::Heat) => hvac.heat(),
+            #[cfg(feature = "cool")]
+            Some(HvacService::Cool) => hvac.cool(),
+            None => hvac.idle(),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use hvac::prelude::*;
+
+#[test]
+#[cfg(not(feature = "cool"))]
+fn heat_without_cool() {
+    let mut hvac = Hvac::default().with_heat(Some(300), Some(60));
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.heat_block_reason(), Some(BlockReason::MinRecover));
+    let state = hvac.tick(60);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.operating_mode(), OperatingMode::Heating);
+    assert_eq!(state.output_mask() & 0b11, 0b01);
+    let state = hvac.idle();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.tick(360);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+#[cfg(not(feature = "cool"))]
+fn state_bits_reserve_cool_without_cool() {
+    assert_eq!(HvacState::from_bits(0b10), None);
+    assert_eq!(HvacState::from_bits(0b11), None);
+    let state = HvacState::from_bits(0b01).unwrap();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.to_bits(), 0b01);
+}
+
+#[test]
+#[cfg(not(feature = "cool"))]
+fn graph_without_cool() {
+    assert_eq!(
+        &Hvac::states()[..2],
+        &[LogicalState::Idle, LogicalState::Heating]
+    );
+    #[cfg(feature = "fan")]
+    assert_eq!(Hvac::states().len(), 4);
+    #[cfg(not(feature = "fan"))]
+    assert_eq!(Hvac::transitions().len(), 2);
+}
+
+#[test]
+#[cfg(feature = "cool")]
+fn would_start_cool_is_false_when_heat_only_and_strict() {
+    let mut hvac = Hvac::default().with_heat_only(true).with_strict(true);
+    let _ = hvac.tick(0);
+    assert!(!hvac.would_start(HvacService::Cool));
+}
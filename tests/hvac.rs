@@ -1,4 +1,16 @@
+#![cfg(all(
+    feature = "fan",
+    feature = "cool",
+    feature = "setpoint",
+    feature = "erv",
+    feature = "zone",
+    feature = "simulate",
+    feature = "stats"
+))]
+#![allow(clippy::bool_assert_comparison)]
+
 use hvac::prelude::*;
+use hvac::MAX_CLAMPED_CONSTRAINT_SECONDS;
 
 #[test]
 fn new_hvac_is_idle() {
@@ -136,6 +148,2840 @@ fn fan_auto_min_run_carries_past_heat() {
     assert_eq!(state.fan, true);
     let state = hvac.idle();
     assert_eq!(state.fan, true);
+    assert_eq!(hvac.fan_held_by_min_run(), true);
     let state = hvac.tick(1);
     assert_eq!(state.fan, false);
+    assert_eq!(hvac.fan_held_by_min_run(), false);
+}
+
+#[test]
+fn block_reason_reports_min_recover() {
+    let mut hvac = Hvac::default().with_heat(None, Some(100));
+    let _ = hvac.tick(0);
+    assert_eq!(hvac.heat_block_reason(), None);
+    let _ = hvac.heat();
+    assert_eq!(hvac.heat_block_reason(), Some(BlockReason::MinRecover));
+    assert_eq!(hvac.cool_block_reason(), None);
+    let _ = hvac.tick(100);
+    assert_eq!(hvac.heat_block_reason(), None);
+}
+
+#[test]
+fn block_reason_reports_opposing_min_run() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(100), None)
+        .with_cool(None, None)
+        .with_fan(None, None);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let _ = hvac.cool();
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::OpposingMinRun));
+    let state = hvac.tick(100);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(hvac.cool_block_reason(), None);
+}
+
+#[test]
+fn block_reason_reports_fan_min_recover() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, Some(100));
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::FanMinRecover));
+    let state = hvac.tick(100);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(hvac.cool_block_reason(), None);
+}
+
+#[test]
+fn tick_near_u32_max_is_monotonic() {
+    let start = u32::MAX - 10;
+    let mut hvac = Hvac::default()
+        .with_heat(Some(5), Some(5))
+        .with_fan(None, None);
+    let state = hvac.tick(start);
+    assert_eq!(state.service, None);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.idle();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    for i in start..start + 5 {
+        let state = hvac.tick(i);
+        assert_eq!(state.service, Some(HvacService::Heat));
+    }
+    let state = hvac.tick(start + 5);
+    assert_eq!(state.service, None);
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    for i in start + 5..start + 10 {
+        let state = hvac.tick(i);
+        assert_eq!(state.service, None);
+    }
+    let state = hvac.tick(u32::MAX);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.tick(u32::MAX);
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn tick_backwards_is_ignored() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(100), None)
+        .with_fan(None, None);
+    let _ = hvac.tick(1000);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let _ = hvac.idle();
+    let state = hvac.tick(0);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.tick(1100);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn zone_aggregator_any_call() {
+    let zones = ZoneAggregator::default();
+    assert_eq!(zones.aggregate(vec![None, None, None]), None);
+    assert_eq!(
+        zones.aggregate(vec![None, Some(HvacService::Cool), None]),
+        Some(HvacService::Cool)
+    );
+    assert_eq!(
+        zones.aggregate(vec![Some(HvacService::Heat), Some(HvacService::Cool), None]),
+        Some(HvacService::Heat)
+    );
+    assert_eq!(
+        zones.aggregate(vec![
+            Some(HvacService::Heat),
+            Some(HvacService::Cool),
+            Some(HvacService::Cool)
+        ]),
+        Some(HvacService::Cool)
+    );
+}
+
+#[test]
+fn zone_aggregator_majority() {
+    let zones = ZoneAggregator::default().with_strategy(ZoneStrategy::Majority);
+    assert_eq!(
+        zones.aggregate(vec![None, Some(HvacService::Heat), None]),
+        None
+    );
+    assert_eq!(
+        zones.aggregate(vec![Some(HvacService::Heat), Some(HvacService::Heat), None]),
+        Some(HvacService::Heat)
+    );
+    let mut hvac = Hvac::default().with_cool(None, None).with_fan(None, None);
+    let state = zones.apply(
+        &mut hvac,
+        vec![Some(HvacService::Cool), None, Some(HvacService::Cool)],
+    );
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let state = zones.apply(&mut hvac, vec![Some(HvacService::Cool), None, None]);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn operating_mode_ignores_timers() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(Some(10), None);
+    let _ = hvac.tick(0);
+    assert_eq!(hvac.operating_mode(), OperatingMode::Idle);
+    let state = hvac.heat();
+    assert_eq!(state.operating_mode(), OperatingMode::Heating);
+    let state = hvac.cool();
+    assert_eq!(state.operating_mode(), OperatingMode::Cooling);
+    let state = hvac.idle();
+    assert_eq!(state.operating_mode(), OperatingMode::FanOnly);
+    let mut other = Hvac::default().with_cool(None, None).with_fan(None, None);
+    let _ = other.tick(500);
+    let _ = other.cool();
+    assert_ne!(hvac, other);
+    assert_eq!(other.operating_mode(), OperatingMode::Cooling);
+}
+
+#[test]
+fn scheduled_setpoint_recovers_on_time() {
+    let mut hvac = Hvac::default().with_heat(None, None).with_fan(None, None);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.set_temperature(100);
+    let _ = hvac.tick(100);
+    let _ = hvac.set_temperature(110);
+    let _ = hvac.tick(200);
+    let _ = hvac.set_temperature(120);
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+    let _ = hvac.tick(1000);
+    let _ = hvac.set_temperature(180);
+    let state = hvac.schedule_setpoint(2000, 200);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(1799);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(1800);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.set_temperature(190);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.set_temperature(200);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn scheduled_setpoint_without_history_recovers_immediately() {
+    let mut hvac = Hvac::default().with_cool(None, None).with_fan(None, None);
+    let _ = hvac.tick(0);
+    let _ = hvac.set_temperature(250);
+    let state = hvac.schedule_setpoint(5000, 220);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let state = hvac.tick(5000);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn scheduled_setpoint_completes_on_overshoot() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None);
+    let _ = hvac.tick(0);
+    let _ = hvac.set_temperature(180);
+    let state = hvac.schedule_setpoint(5000, 200);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.set_temperature(205);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(10);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn resuming_applies_min_run_from_resume_time() {
+    let observed = HvacState {
+        service: Some(HvacService::Cool),
+        fan: true,
+        economizing: false,
+        freeze_protecting: false,
+        erv: false,
+        powered: true,
+        short_cycling: false,
+        capacity_warning: false,
+        undersampled: false,
+        starting_up: false,
+        valve_settling: false,
+    };
+    let mut hvac = Hvac::resuming(observed, 1000)
+        .with_cool(Some(300), Some(300))
+        .with_fan(None, None);
+    let state = hvac.idle();
+    assert_eq!(state, observed);
+    let state = hvac.tick(1299);
+    assert_eq!(state, observed);
+    let state = hvac.tick(1300);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn resuming_idle_applies_min_recover_from_resume_time() {
+    let observed = HvacState {
+        service: None,
+        fan: false,
+        economizing: false,
+        freeze_protecting: false,
+        erv: false,
+        powered: true,
+        short_cycling: false,
+        capacity_warning: false,
+        undersampled: false,
+        starting_up: false,
+        valve_settling: false,
+    };
+    let mut hvac = Hvac::resuming(observed, 1000).with_heat(None, Some(60));
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    let state = hvac.tick(1059);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(1060);
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+struct MockController {
+    state: HvacState,
+    ticks: u32,
+}
+
+impl HvacController for MockController {
+    fn tick(&mut self, _current_seconds: u32) -> HvacState {
+        self.ticks += 1;
+        self.state
+    }
+
+    fn heat(&mut self) -> HvacState {
+        self.state.service = Some(HvacService::Heat);
+        self.state
+    }
+
+    fn cool(&mut self) -> HvacState {
+        self.state.service = Some(HvacService::Cool);
+        self.state
+    }
+
+    fn fan_auto(&mut self, fan_auto: bool) -> HvacState {
+        self.state.fan = !fan_auto;
+        self.state
+    }
+
+    fn idle(&mut self) -> HvacState {
+        self.state.service = None;
+        self.state
+    }
+}
+
+fn call_for_heat<C: HvacController>(controller: &mut C, current_seconds: u32) -> HvacState {
+    let _ = controller.heat();
+    controller.tick(current_seconds)
+}
+
+#[test]
+fn controller_trait_accepts_hvac_and_mocks() {
+    let mut hvac = Hvac::default().with_heat(None, None).with_fan(None, None);
+    let state = call_for_heat(&mut hvac, 0);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let mut mock = MockController {
+        state: HvacState {
+            service: None,
+            fan: false,
+            economizing: false,
+            freeze_protecting: false,
+            erv: false,
+            powered: true,
+            short_cycling: false,
+            capacity_warning: false,
+            undersampled: false,
+            starting_up: false,
+            valve_settling: false,
+        },
+        ticks: 0,
+    };
+    let state = call_for_heat(&mut mock, 0);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(mock.ticks, 1);
+}
+
+#[test]
+fn fan_min_run_honored_after_short_heat() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(Some(60), None);
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.fan, true);
+    let _ = hvac.tick(2);
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    for i in 2..60 {
+        let state = hvac.tick(i);
+        assert_eq!(state.fan, true);
+    }
+    let state = hvac.tick(60);
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn fan_min_run_honored_after_manual_start() {
+    let mut hvac = Hvac::default().with_fan(Some(60), None);
+    let _ = hvac.tick(1000);
+    let state = hvac.fan_auto(false);
+    assert_eq!(state.fan, true);
+    let state = hvac.fan_auto(true);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(1059);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(1060);
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn fan_min_recover_honored_after_auto_stop() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(Some(10), Some(30));
+    let _ = hvac.tick(100);
+    let _ = hvac.heat();
+    let state = hvac.idle();
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(110);
+    assert_eq!(state.fan, false);
+    let state = hvac.fan_auto(false);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(139);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(140);
+    assert_eq!(state.fan, true);
+}
+
+#[test]
+fn economizer_runs_fan_and_suppresses_cool() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_economizer_yield(Some(100));
+    let _ = hvac.tick(0);
+    let state = hvac.economize(true);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    assert_eq!(state.economizing, true);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(state.economizing, true);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::Economizer));
+    let state = hvac.tick(99);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(100);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(state.fan, true);
+    assert_eq!(state.economizing, false);
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    assert_eq!(state.economizing, true);
+    let state = hvac.economize(false);
+    assert_eq!(state.fan, false);
+    assert_eq!(state.economizing, false);
+}
+
+#[test]
+fn economizer_yields_to_heat() {
+    let mut hvac = Hvac::default().with_heat(None, None).with_fan(None, None);
+    let _ = hvac.tick(0);
+    let _ = hvac.economize(true);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.economizing, false);
+}
+
+#[test]
+fn validate_reports_constraints_that_never_elapse() {
+    assert_eq!(Hvac::default().validate(), Ok(()));
+    assert_eq!(
+        Hvac::default().with_heat(Some(u32::MAX), None).validate(),
+        Err(HvacConfigError::HeatConstraintNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default().with_cool(None, Some(u32::MAX)).validate(),
+        Err(HvacConfigError::CoolConstraintNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default().with_fan(Some(u32::MAX), None).validate(),
+        Err(HvacConfigError::FanConstraintNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default().with_start_stagger(u32::MAX).validate(),
+        Err(HvacConfigError::FanConstraintNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default()
+            .with_changeover_delay(Some(u32::MAX), None)
+            .validate(),
+        Err(HvacConfigError::ChangeoverDelayNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default()
+            .with_changeover_delay(None, Some(u32::MAX))
+            .validate(),
+        Err(HvacConfigError::ChangeoverDelayNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default().with_auto_hold(u32::MAX).validate(),
+        Err(HvacConfigError::ChangeoverDelayNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default().with_erv(None, Some(u32::MAX)).validate(),
+        Err(HvacConfigError::ErvConstraintNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default()
+            .with_shared_compressor_recover(u32::MAX)
+            .with_heat(None, None)
+            .with_cool(None, None)
+            .validate(),
+        Err(HvacConfigError::SharedCompressorRecoverNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default().with_startup_delay(u32::MAX).validate(),
+        Err(HvacConfigError::StartupDelayNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default().with_call_debounce(u32::MAX).validate(),
+        Err(HvacConfigError::CallDebounceNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default()
+            .with_min_call_duration(HvacService::Cool, u32::MAX)
+            .validate(),
+        Err(HvacConfigError::CallDebounceNeverElapses)
+    );
+    assert_eq!(
+        Hvac::default().with_valve_settle(u32::MAX).validate(),
+        Err(HvacConfigError::ValveSettleNeverElapses)
+    );
+}
+
+#[test]
+fn validate_reports_circulate_that_never_cycles() {
+    assert_eq!(Hvac::default().with_circulate(600, 1200).validate(), Ok(()));
+    assert_eq!(
+        Hvac::default().with_circulate(0, 0).validate(),
+        Err(HvacConfigError::CirculateNeverCycles)
+    );
+    assert_eq!(
+        Hvac::default().with_circulate(u32::MAX, 1).validate(),
+        Err(HvacConfigError::CirculateNeverCycles)
+    );
+}
+
+#[test]
+fn validate_reports_cool_coil_overrun_that_never_elapses() {
+    assert_eq!(
+        Hvac::default()
+            .with_cool_coil_overrun(100, u32::MAX)
+            .validate(),
+        Err(HvacConfigError::FanConstraintNeverElapses)
+    );
+}
+
+#[test]
+fn validate_reports_economizer_yield_that_never_elapses() {
+    assert_eq!(
+        Hvac::default().with_economizer_yield(None).validate(),
+        Ok(())
+    );
+    assert_eq!(
+        Hvac::default()
+            .with_economizer_yield(Some(u32::MAX))
+            .validate(),
+        Err(HvacConfigError::EconomizerYieldNeverElapses)
+    );
+}
+
+#[test]
+fn validate_reports_inverted_freeze_protection() {
+    assert_eq!(
+        Hvac::default().with_freeze_protection(0, 50).validate(),
+        Ok(())
+    );
+    assert_eq!(
+        Hvac::default().with_freeze_protection(50, 0).validate(),
+        Err(HvacConfigError::FreezeProtectionInverted)
+    );
+}
+
+#[test]
+fn validate_reports_time_scale_that_stops_time() {
+    assert_eq!(Hvac::default().with_time_scale(0, 0).validate(), Ok(()));
+    assert_eq!(
+        Hvac::default().with_time_scale(0, 1).validate(),
+        Err(HvacConfigError::TimeScaleStopsTime)
+    );
+}
+
+#[test]
+fn fan_not_held_by_min_run_with_demand() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(Some(60), None);
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.fan_held_by_min_run(), false);
+    let _ = hvac.idle();
+    let state = hvac.fan_auto(false);
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.fan_held_by_min_run(), false);
+}
+
+#[test]
+fn start_stagger_defers_service_behind_fan() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_start_stagger(5);
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.heat_block_reason(), Some(BlockReason::StartStagger));
+    let state = hvac.tick(4);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(5);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(hvac.heat_block_reason(), None);
+}
+
+#[test]
+fn start_stagger_skipped_when_fan_already_running() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_start_stagger(5);
+    let _ = hvac.tick(0);
+    let state = hvac.fan_auto(false);
+    assert_eq!(state.fan, true);
+    let _ = hvac.tick(1);
+    let state = hvac.cool();
+    assert_eq!(state.service, Some(HvacService::Cool));
+}
+
+#[test]
+fn duty_cycle_over_trailing_window() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None);
+    assert_eq!(hvac.duty_cycle(HvacService::Heat, 100), None);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.tick(30);
+    let _ = hvac.idle();
+    let _ = hvac.tick(50);
+    let _ = hvac.heat();
+    let _ = hvac.tick(100);
+    assert_eq!(hvac.duty_cycle(HvacService::Heat, 100), Some(80));
+    assert_eq!(hvac.duty_cycle(HvacService::Heat, 50), Some(100));
+    assert_eq!(hvac.duty_cycle(HvacService::Cool, 100), Some(0));
+    assert_eq!(hvac.duty_cycle(HvacService::Heat, 0), None);
+}
+
+#[test]
+fn duty_cycle_unknown_beyond_history() {
+    let mut hvac = Hvac::default().with_cool(None, None).with_fan(None, None);
+    for i in 0..20 {
+        let _ = hvac.tick(i * 10);
+        let _ = hvac.cool();
+        let _ = hvac.tick(i * 10 + 5);
+        let _ = hvac.idle();
+    }
+    assert_eq!(hvac.duty_cycle(HvacService::Cool, 100), Some(50));
+    assert_eq!(hvac.duty_cycle(HvacService::Cool, 195), None);
+}
+
+#[test]
+fn hydronic_heat_runs_without_fan() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_fan_on_heat(false);
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.fan, false);
+    let state = hvac.cool();
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(state.fan, true);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.fan, false);
+    let state = hvac.fan_auto(false);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.fan, true);
+}
+
+#[test]
+fn checked_tick_reports_min_run_holds() {
+    let mut hvac = Hvac::default()
+        .with_cool(Some(100), None)
+        .with_fan(Some(150), None);
+    let (state, outcome) = hvac.checked_tick(0);
+    assert_eq!(state.service, None);
+    assert_eq!(outcome, TickOutcome::Unchanged);
+    let _ = hvac.cool();
+    let (state, outcome) = hvac.checked_tick(1);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(outcome, TickOutcome::Unchanged);
+    let _ = hvac.idle();
+    let (state, outcome) = hvac.checked_tick(40);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(outcome, TickOutcome::HeldByMinRun(HvacService::Cool, 60));
+    let (state, outcome) = hvac.checked_tick(100);
+    assert_eq!(state.service, None);
+    assert_eq!(outcome, TickOutcome::FanHeldByMinRun(50));
+    let (state, outcome) = hvac.checked_tick(150);
+    assert_eq!(state.fan, false);
+    assert_eq!(outcome, TickOutcome::Changed);
+}
+
+#[test]
+fn raw_outputs_honor_polarity() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_output_polarity(false, false, true);
+    let _ = hvac.tick(0);
+    assert_eq!(
+        hvac.raw_outputs(),
+        RawOutputs {
+            heat: false,
+            cool: false,
+            fan: true,
+        }
+    );
+    let _ = hvac.heat();
+    assert_eq!(
+        hvac.raw_outputs(),
+        RawOutputs {
+            heat: true,
+            cool: false,
+            fan: false,
+        }
+    );
+}
+
+#[test]
+fn merge_prefers_primary_unless_faulted() {
+    let mut primary = Hvac::default().with_heat(None, None).with_fan(None, None);
+    let mut backup = Hvac::default().with_heat(None, None).with_fan(None, None);
+    let _ = primary.tick(0);
+    let _ = backup.tick(0);
+    let _ = backup.heat();
+    let state = Hvac::merge(&primary, &backup, true);
+    assert_eq!(state.service, None);
+    let state = Hvac::merge(&primary, &backup, false);
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn repeated_tick_reflects_intervening_calls() {
+    let mut hvac = Hvac::default().with_heat(None, None).with_fan(None, None);
+    let state = hvac.tick(10);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(10);
+    assert_eq!(state.service, None);
+    let _ = hvac.heat();
+    let state = hvac.tick(10);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let mut hvac = hvac.with_heat(Some(100), None);
+    let _ = hvac.idle();
+    let state = hvac.tick(10);
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn fan_only_clears_calls_and_runs_fan() {
+    let mut hvac = Hvac::default().with_cool(None, None).with_fan(None, None);
+    let _ = hvac.tick(0);
+    let state = hvac.cool();
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let state = hvac.fan_only();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    let state = hvac.fan_auto(true);
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn conflict_policy_resolves_simultaneous_calls() {
+    let suppressed = Some(BlockReason::ConflictPolicy);
+    let policies = [
+        (
+            ConflictPolicy::LastWins,
+            Some(HvacService::Cool),
+            None,
+            None,
+        ),
+        (
+            ConflictPolicy::HeatPriority,
+            Some(HvacService::Heat),
+            None,
+            suppressed,
+        ),
+        (
+            ConflictPolicy::CoolPriority,
+            Some(HvacService::Cool),
+            suppressed,
+            None,
+        ),
+        (ConflictPolicy::Ignore, None, suppressed, suppressed),
+    ];
+    for &(policy, expected, heat_reason, cool_reason) in policies.iter() {
+        let mut hvac = Hvac::default()
+            .with_heat(None, None)
+            .with_cool(None, None)
+            .with_fan(None, None)
+            .with_conflict_policy(policy);
+        let _ = hvac.tick(0);
+        let _ = hvac.heat();
+        let state = hvac.cool();
+        assert_eq!(state.service, expected);
+        assert_eq!(hvac.heat_block_reason(), heat_reason);
+        assert_eq!(hvac.cool_block_reason(), cool_reason);
+        let state = hvac.idle();
+        assert_eq!(state.service, None);
+        assert_eq!(hvac.heat_block_reason(), None);
+        assert_eq!(hvac.cool_block_reason(), None);
+    }
+}
+
+#[test]
+fn conflict_policy_resolves_simultaneous_timer_expiry() {
+    let policies = [
+        (ConflictPolicy::LastWins, Some(HvacService::Cool)),
+        (ConflictPolicy::HeatPriority, Some(HvacService::Heat)),
+        (ConflictPolicy::CoolPriority, Some(HvacService::Cool)),
+        (ConflictPolicy::Ignore, None),
+    ];
+    for &(policy, expected) in policies.iter() {
+        let mut hvac = Hvac::default()
+            .with_heat(None, Some(60))
+            .with_cool(None, Some(60))
+            .with_fan(None, None)
+            .with_conflict_policy(policy);
+        let _ = hvac.tick(0);
+        let _ = hvac.heat();
+        let state = hvac.cool();
+        assert_eq!(state.service, None);
+        let state = hvac.tick(59);
+        assert_eq!(state.service, None);
+        let state = hvac.tick(60);
+        assert_eq!(state.service, expected);
+    }
+}
+
+#[test]
+fn compressor_active_ignores_fan() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None);
+    let _ = hvac.tick(0);
+    let _ = hvac.fan_only();
+    assert_eq!(hvac.compressor_active(), false);
+    let _ = hvac.heat();
+    assert_eq!(hvac.compressor_active(), true);
+    let _ = hvac.cool();
+    assert_eq!(hvac.compressor_active(), true);
+    let _ = hvac.idle();
+    assert_eq!(hvac.compressor_active(), false);
+}
+
+#[test]
+fn fan_speed_ramps_each_tick() {
+    let mut hvac = Hvac::default().with_fan(None, None).with_fan_ramp(100);
+    let _ = hvac.tick(0);
+    assert_eq!(hvac.fan_speed(), 0);
+    let state = hvac.fan_only();
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.fan_speed(), 0);
+    let _ = hvac.tick(1);
+    assert_eq!(hvac.fan_speed(), 100);
+    let _ = hvac.tick(2);
+    assert_eq!(hvac.fan_speed(), 200);
+    let _ = hvac.tick(3);
+    assert_eq!(hvac.fan_speed(), 255);
+    let _ = hvac.fan_auto(true);
+    let _ = hvac.tick(4);
+    assert_eq!(hvac.fan_speed(), 155);
+}
+
+#[test]
+fn fan_speed_without_ramp_is_immediate() {
+    let mut hvac = Hvac::default().with_fan(None, None);
+    let _ = hvac.tick(0);
+    let _ = hvac.fan_only();
+    assert_eq!(hvac.fan_speed(), 255);
+    let _ = hvac.fan_auto(true);
+    assert_eq!(hvac.fan_speed(), 0);
+}
+
+#[test]
+fn simulate_reports_trajectory_statistics() {
+    let config = Hvac::default()
+        .with_heat(Some(100), Some(50))
+        .with_fan(None, None);
+    let events = [
+        (0, HvacCommand::Heat),
+        (50, HvacCommand::Tick),
+        (60, HvacCommand::Idle),
+        (150, HvacCommand::Tick),
+        (200, HvacCommand::Heat),
+        (300, HvacCommand::Idle),
+        (400, HvacCommand::Tick),
+    ];
+    let stats = simulate(&config, &events);
+    assert_eq!(stats.heat_cycles, 2);
+    assert_eq!(stats.cool_cycles, 0);
+    assert_eq!(stats.heat_runtime_seconds, 200);
+    assert_eq!(stats.fan_runtime_seconds, 200);
+    assert_eq!(stats.max_continuous_run_seconds, 100);
+    assert_eq!(stats.blocked_seconds, 50);
+}
+
+#[test]
+fn cool_post_run_off_follows_completed_cycle() {
+    let mut hvac = Hvac::default()
+        .with_cool(Some(100), Some(50))
+        .with_cool_post_run_off(Some(200))
+        .with_fan(None, None);
+    let _ = hvac.tick(1000);
+    let state = hvac.cool();
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let _ = hvac.idle();
+    let state = hvac.tick(1100);
+    assert_eq!(state.service, None);
+    let _ = hvac.cool();
+    let state = hvac.tick(1150);
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::MinRecover));
+    let state = hvac.tick(1299);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(1300);
+    assert_eq!(state.service, Some(HvacService::Cool));
+}
+
+#[test]
+fn cool_post_stop_recover_reports_configured_off_time() {
+    let hvac = Hvac::default().with_cool(Some(100), Some(50));
+    assert_eq!(hvac.cool_post_stop_recover(), Some(50));
+    let hvac = hvac.with_cool_post_run_off(Some(200));
+    assert_eq!(hvac.cool_post_stop_recover(), Some(200));
+    let hvac = Hvac::default().with_cool(None, None);
+    assert_eq!(hvac.cool_post_stop_recover(), None);
+
+    let mut hvac = Hvac::default()
+        .with_cool(None, Some(60))
+        .with_fan(None, None)
+        .with_proportional_recover(HvacService::Cool, 100);
+    let _ = hvac.tick(60);
+    let _ = hvac.cool();
+    assert_eq!(hvac.cool_post_stop_recover(), Some(60));
+    let _ = hvac.tick(1060);
+    assert_eq!(hvac.cool_post_stop_recover(), Some(1000));
+    let _ = hvac.idle();
+    assert_eq!(hvac.effective_cool_recover(), Some(1000));
+}
+
+#[test]
+fn time_scale_accelerates_constraints() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, Some(600))
+        .with_fan(None, None)
+        .with_time_scale(60, 1);
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    let state = hvac.tick(9);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(10);
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn time_scale_truncates_fractional_ratios() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, Some(10))
+        .with_fan(None, None)
+        .with_time_scale(2, 3);
+    let _ = hvac.cool();
+    let state = hvac.tick(14);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(15);
+    assert_eq!(state.service, Some(HvacService::Cool));
+}
+
+#[test]
+fn armed_service_prewarms_fan() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_start_stagger(5);
+    let _ = hvac.tick(100);
+    let state = hvac.arm_service(HvacService::Heat, 10);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(104);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(105);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(109);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(110);
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn armed_service_prewarms_fan_only_before_call() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_heat_fan_config(ServiceFanConfig {
+            prepurge_seconds: 20,
+            ..ServiceFanConfig::default()
+        });
+    let _ = hvac.tick(10);
+    let state = hvac.arm_service(HvacService::Heat, 3600);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(3559);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(3590);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(3610);
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn armed_uncoupled_service_skips_prewarm() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_start_stagger(5)
+        .with_fan_on_heat(false);
+    let _ = hvac.tick(100);
+    let state = hvac.arm_service(HvacService::Heat, 10);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(109);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(110);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn fan_only_clears_armed_service() {
+    let mut hvac = Hvac::default().with_heat(None, None).with_fan(None, None);
+    let _ = hvac.tick(0);
+    let _ = hvac.arm_service(HvacService::Heat, 10);
+    let state = hvac.fan_only();
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(10);
+    assert_eq!(state.service, None);
+    let state = hvac.fan_auto(true);
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn idle_clears_armed_service() {
+    let mut hvac = Hvac::default().with_cool(None, None).with_fan(None, None);
+    let _ = hvac.tick(0);
+    let _ = hvac.arm_service(HvacService::Cool, 10);
+    let state = hvac.idle();
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(10);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn state_idle_and_active_predicates() {
+    let services = [None, Some(HvacService::Heat), Some(HvacService::Cool)];
+    for &service in services.iter() {
+        for &fan in [false, true].iter() {
+            let state = HvacState {
+                service,
+                fan,
+                economizing: false,
+                freeze_protecting: false,
+                erv: false,
+                powered: true,
+                short_cycling: false,
+                capacity_warning: false,
+                undersampled: false,
+                starting_up: false,
+                valve_settling: false,
+            };
+            let idle = service.is_none() && !fan;
+            assert_eq!(state.is_idle(), idle);
+            assert_eq!(state.is_active(), !idle);
+        }
+    }
+}
+
+#[test]
+fn erv_runs_independently_with_own_constraints() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_erv(Some(30), Some(20));
+    let _ = hvac.tick(100);
+    let state = hvac.erv(true);
+    assert_eq!(state.erv, true);
+    assert_eq!(state.fan, false);
+    assert_eq!(state.is_active(), true);
+    let state = hvac.heat();
+    assert_eq!(state.erv, true);
+    assert_eq!(state.fan, true);
+    let state = hvac.erv(false);
+    assert_eq!(state.erv, true);
+    let state = hvac.tick(130);
+    assert_eq!(state.erv, false);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.erv(true);
+    assert_eq!(state.erv, false);
+    let state = hvac.tick(150);
+    assert_eq!(state.erv, true);
+}
+
+#[test]
+fn fan_recover_depends_on_stop_reason() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_fan_recover(Some(10), Some(100));
+    let _ = hvac.tick(1000);
+    let state = hvac.fan_auto(false);
+    assert_eq!(state.fan, true);
+    let state = hvac.fan_auto(true);
+    assert_eq!(state.fan, false);
+    let state = hvac.fan_auto(false);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(1099);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(1100);
+    assert_eq!(state.fan, true);
+    let _ = hvac.fan_auto(true);
+    let _ = hvac.tick(1200);
+    let state = hvac.heat();
+    assert_eq!(state.fan, true);
+    let state = hvac.idle();
+    assert_eq!(state.fan, false);
+    let _ = hvac.fan_auto(false);
+    let state = hvac.tick(1209);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(1210);
+    assert_eq!(state.fan, true);
+}
+
+#[test]
+fn tick_batch_applies_commands_then_ticks() {
+    let mut hvac = Hvac::default()
+        .with_cool(Some(100), None)
+        .with_fan(None, None);
+    let state = hvac.tick_batch(&[
+        (0, Some(HvacCommand::Cool)),
+        (10, Some(HvacCommand::Idle)),
+        (50, None),
+    ]);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let state = hvac.tick_batch(&[(40, None), (100, None)]);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn auto_hold_limits_changeover_rate() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_auto_hold(100);
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let _ = hvac.tick(10);
+    let state = hvac.cool();
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let _ = hvac.tick(20);
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.heat_block_reason(), Some(BlockReason::AutoHold));
+    let state = hvac.tick(109);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(110);
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn auto_hold_allows_same_service_restart() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_auto_hold(100);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.tick(10);
+    let _ = hvac.cool();
+    let _ = hvac.tick(20);
+    let _ = hvac.idle();
+    let _ = hvac.tick(30);
+    let state = hvac.cool();
+    assert_eq!(state.service, Some(HvacService::Cool));
+}
+
+#[test]
+fn last_tick_advanced_detects_frozen_clock() {
+    let mut hvac = Hvac::default();
+    assert_eq!(hvac.last_tick_advanced(), false);
+    let _ = hvac.tick(0);
+    assert_eq!(hvac.last_tick_advanced(), true);
+    let _ = hvac.tick(10);
+    assert_eq!(hvac.last_tick_advanced(), true);
+    let _ = hvac.tick(10);
+    assert_eq!(hvac.last_tick_advanced(), false);
+    let _ = hvac.tick(5);
+    assert_eq!(hvac.last_tick_advanced(), false);
+    let _ = hvac.tick(11);
+    assert_eq!(hvac.last_tick_advanced(), true);
+}
+
+#[test]
+fn reconfigure_preserves_timing_history() {
+    let mut hvac = Hvac::default();
+    let _ = hvac.tick(0);
+    let _ = hvac.tick(60);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    hvac.reconfigure(HvacConfig {
+        heat_min_run_seconds: Some(120),
+        fan_auto: false,
+        ..HvacConfig::default()
+    });
+    let state = hvac.idle();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.tick(179);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.tick(180);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+}
+
+#[test]
+fn queries_before_first_tick() {
+    let hvac = Hvac::default();
+    assert_eq!(hvac.has_ticked(), false);
+    assert_eq!(hvac.last_tick_advanced(), false);
+    assert_eq!(hvac.duty_cycle(HvacService::Heat, 60), None);
+    assert_eq!(hvac.duty_cycle(HvacService::Cool, 60), None);
+    assert_eq!(hvac.heat_block_reason(), None);
+    assert_eq!(hvac.cool_block_reason(), None);
+    assert_eq!(hvac.fan_held_by_min_run(), false);
+    assert_eq!(hvac.fan_min_run_remaining(), None);
+    assert_eq!(hvac.pending_waits().count(), 4);
+    assert_eq!(
+        hvac.pending_waits()
+            .find(|(kind, _)| *kind == TimerKind::Cool),
+        Some((TimerKind::Cool, 300))
+    );
+    assert_eq!(hvac.cool_post_stop_recover(), Some(300));
+    assert_eq!(hvac.compressor_active(), false);
+    assert_eq!(hvac.fan_speed(), 0);
+    assert_eq!(hvac.operating_mode(), OperatingMode::Idle);
+
+    let mut hvac = Hvac::default();
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.has_ticked(), false);
+    assert_eq!(hvac.heat_block_reason(), Some(BlockReason::MinRecover));
+    let _ = hvac.tick(0);
+    assert_eq!(hvac.has_ticked(), true);
+}
+
+#[test]
+fn unoccupied_deadband_defers_recovery() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_unoccupied_deadband(20);
+    let _ = hvac.tick(0);
+    let _ = hvac.set_occupancy(false);
+    let _ = hvac.set_temperature(235);
+    let state = hvac.schedule_setpoint(5000, 220);
+    assert_eq!(state.service, None);
+    let state = hvac.set_temperature(245);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let state = hvac.set_temperature(230);
+    assert_eq!(state.service, Some(HvacService::Cool));
+}
+
+#[test]
+fn occupied_ignores_unoccupied_deadband() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_unoccupied_deadband(20);
+    let _ = hvac.tick(0);
+    let _ = hvac.set_occupancy(false);
+    let _ = hvac.set_temperature(235);
+    let state = hvac.schedule_setpoint(5000, 220);
+    assert_eq!(state.service, None);
+    let state = hvac.set_occupancy(true);
+    assert_eq!(state.service, Some(HvacService::Cool));
+}
+
+#[test]
+fn fan_min_run_remaining_during_service() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(Some(100), None);
+    let _ = hvac.tick(0);
+    assert_eq!(hvac.fan_min_run_remaining(), None);
+    let _ = hvac.heat();
+    assert_eq!(hvac.fan_min_run_remaining(), Some(100));
+    let _ = hvac.tick(40);
+    assert_eq!(hvac.fan_min_run_remaining(), Some(60));
+    let state = hvac.idle();
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.fan_min_run_remaining(), Some(60));
+    let state = hvac.tick(100);
+    assert_eq!(state.fan, false);
+    assert_eq!(hvac.fan_min_run_remaining(), None);
+}
+
+#[test]
+fn pending_waits_lists_running_timers() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(100), None)
+        .with_cool(None, None)
+        .with_fan(Some(30), None)
+        .with_erv(None, None);
+    let _ = hvac.tick(0);
+    assert_eq!(hvac.pending_waits().next(), None);
+    let _ = hvac.heat();
+    let _ = hvac.tick(10);
+    let mut waits = hvac.pending_waits();
+    assert_eq!(waits.next(), Some((TimerKind::Heat, 90)));
+    assert_eq!(waits.next(), Some((TimerKind::Fan, 20)));
+    assert_eq!(waits.next(), None);
+}
+
+#[test]
+fn changeover_delay_heat_to_cool() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_changeover_delay(Some(100), Some(300));
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.tick(10);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::ChangeoverDelay));
+    let state = hvac.tick(109);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(110);
+    assert_eq!(state.service, Some(HvacService::Cool));
+}
+
+#[test]
+fn changeover_delay_cool_to_heat() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_changeover_delay(Some(100), Some(300));
+    let _ = hvac.tick(0);
+    let _ = hvac.cool();
+    let _ = hvac.tick(10);
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    let state = hvac.tick(309);
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.heat_block_reason(), Some(BlockReason::ChangeoverDelay));
+    let state = hvac.tick(310);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let _ = hvac.idle();
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn last_service_retained_while_idle() {
+    let mut hvac = Hvac::default().with_cool(None, None).with_fan(None, None);
+    assert_eq!(hvac.last_service(), None);
+    let _ = hvac.tick(0);
+    let _ = hvac.cool();
+    assert_eq!(hvac.last_service(), Some(HvacService::Cool));
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.last_service(), Some(HvacService::Cool));
+
+    let hvac = Hvac::resuming(
+        HvacState {
+            service: Some(HvacService::Heat),
+            fan: true,
+            economizing: false,
+            freeze_protecting: false,
+            erv: false,
+            powered: true,
+            short_cycling: false,
+            capacity_warning: false,
+            undersampled: false,
+            starting_up: false,
+            valve_settling: false,
+        },
+        100,
+    );
+    assert_eq!(hvac.last_service(), Some(HvacService::Heat));
+}
+
+#[test]
+fn would_start_leaves_state_untouched() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, Some(60))
+        .with_cool(None, None)
+        .with_fan(None, None);
+    let _ = hvac.tick(0);
+    assert_eq!(hvac.would_start(HvacService::Heat), false);
+    assert_eq!(hvac.would_start(HvacService::Cool), true);
+    assert_eq!(hvac.heat_block_reason(), None);
+    let state = hvac.tick(60);
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.would_start(HvacService::Heat), true);
+    let _ = hvac.cool();
+    assert_eq!(hvac.would_start(HvacService::Cool), true);
+}
+
+#[test]
+fn take_runtime_stats_resets_counters() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.tick(100);
+    let _ = hvac.cool();
+    let _ = hvac.tick(150);
+    let stats = hvac.take_runtime_stats();
+    assert_eq!(stats.heat_runtime_seconds, 100);
+    assert_eq!(stats.cool_runtime_seconds, 50);
+    assert_eq!(stats.fan_runtime_seconds, 150);
+    assert_eq!(stats.heat_cycles, 1);
+    assert_eq!(stats.cool_cycles, 1);
+    let state = hvac.tick(200);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let stats = hvac.take_runtime_stats();
+    assert_eq!(stats.heat_runtime_seconds, 0);
+    assert_eq!(stats.cool_runtime_seconds, 50);
+    assert_eq!(stats.cool_cycles, 0);
+}
+
+#[test]
+fn test_output_honors_compressor_protection() {
+    let mut hvac = Hvac::default()
+        .with_cool(Some(300), Some(300))
+        .with_fan(None, None);
+    let _ = hvac.tick(0);
+    let (state, blocked) = hvac.test_output(Some(HvacService::Cool), false);
+    assert_eq!(state.service, None);
+    assert_eq!(blocked, Some(BlockReason::MinRecover));
+    let _ = hvac.tick(300);
+    let (state, blocked) = hvac.test_output(Some(HvacService::Cool), false);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(blocked, None);
+    let (state, blocked) = hvac.test_output(None, true);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(state.fan, true);
+    assert_eq!(blocked, None);
+    let state = hvac.tick(600);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+}
+
+#[test]
+fn test_output_bypasses_economizer_and_call_gates() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_call_debounce(60)
+        .with_cool_humidity_lockout(Some(40))
+        .with_conflict_policy(ConflictPolicy::Ignore);
+    let _ = hvac.tick(0);
+    let _ = hvac.economize(true);
+    let _ = hvac.set_humidity(30);
+    let _ = hvac.heat();
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    let (state, blocked) = hvac.test_output(Some(HvacService::Cool), false);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(state.economizing, false);
+    assert_eq!(blocked, None);
+    let (state, blocked) = hvac.test_output(None, false);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, false);
+    assert_eq!(blocked, None);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(state.economizing, true);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::CallDebounce));
+}
+
+#[test]
+fn proportional_recover_scales_with_run_length() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, Some(60))
+        .with_fan(None, None)
+        .with_proportional_recover(HvacService::Heat, 50);
+    let _ = hvac.tick(60);
+    let _ = hvac.heat();
+    let _ = hvac.tick(460);
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    let state = hvac.tick(659);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(660);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let _ = hvac.tick(670);
+    let _ = hvac.idle();
+    let _ = hvac.heat();
+    let state = hvac.tick(729);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(730);
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn proportional_recover_is_capped() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_proportional_recover(HvacService::Cool, 1000);
+    let _ = hvac.tick(0);
+    let _ = hvac.cool();
+    let _ = hvac.tick(10_000);
+    let _ = hvac.idle();
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    let state = hvac.tick(13_599);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(13_600);
+    assert_eq!(state.service, Some(HvacService::Cool));
+}
+
+#[test]
+fn evaluate_matches_tick() {
+    let mut ticked = Hvac::default().with_heat(None, Some(60));
+    let mut evaluated = ticked;
+    let _ = ticked.heat();
+    let _ = evaluated.heat();
+    for seconds in [0, 30, 30, 60, 90] {
+        assert_eq!(evaluated.evaluate(seconds), ticked.tick(seconds));
+    }
+    assert_eq!(evaluated.has_ticked(), true);
+    assert_eq!(evaluated.last_tick_advanced(), true);
+}
+
+#[test]
+fn state_bits_round_trip() {
+    let mut valid = 0;
+    for bits in 0..=u8::MAX {
+        if let Some(state) = HvacState::from_bits(bits) {
+            assert_eq!(state.to_bits(), bits);
+            valid += 1;
+        };
+    }
+    assert_eq!(valid, 3 * 2 * 2 * 2 * 2 * 2);
+    let state = HvacState {
+        service: Some(HvacService::Cool),
+        fan: true,
+        economizing: false,
+        freeze_protecting: false,
+        erv: true,
+        powered: true,
+        short_cycling: false,
+        capacity_warning: false,
+        undersampled: false,
+        starting_up: false,
+        valve_settling: false,
+    };
+    assert_eq!(state.to_bits(), 0b1_0110);
+    assert_eq!(HvacState::from_bits(0b1_0110), Some(state));
+    assert_eq!(HvacState::from_bits(0b11), None);
+    assert_eq!(HvacState::from_bits(0b1000_0000), None);
+}
+
+static TRANSITIONS: core::sync::atomic::AtomicU32 = core::sync::atomic::AtomicU32::new(0);
+
+fn count_transition(transition: HvacTransition) {
+    assert_ne!(transition.from, transition.to);
+    let _ = TRANSITIONS.fetch_add(1, core::sync::atomic::Ordering::SeqCst);
+}
+
+#[test]
+fn on_transition_called_when_outputs_change() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(Some(30), None);
+    hvac.on_transition(count_transition);
+    let _ = hvac.tick(0);
+    let _ = hvac.would_start(HvacService::Heat);
+    assert_eq!(TRANSITIONS.load(core::sync::atomic::Ordering::SeqCst), 0);
+    let _ = hvac.heat();
+    assert_eq!(TRANSITIONS.load(core::sync::atomic::Ordering::SeqCst), 1);
+    let _ = hvac.tick(10);
+    let _ = hvac.idle();
+    assert_eq!(TRANSITIONS.load(core::sync::atomic::Ordering::SeqCst), 2);
+    let _ = hvac.tick(20);
+    assert_eq!(TRANSITIONS.load(core::sync::atomic::Ordering::SeqCst), 2);
+    let _ = hvac.tick(30);
+    assert_eq!(TRANSITIONS.load(core::sync::atomic::Ordering::SeqCst), 3);
+}
+
+#[test]
+fn fan_auto_as_service_ends_honors_fan_min_run() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(Some(60), None);
+    let _ = hvac.tick(0);
+    let _ = hvac.cool();
+    let _ = hvac.fan_auto(false);
+    let _ = hvac.tick(30);
+    let state = hvac.fan_auto(true);
+    assert_eq!(state.fan, true);
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(59);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(60);
+    assert_eq!(state.fan, false);
+
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(Some(60), None);
+    let _ = hvac.tick(0);
+    let _ = hvac.cool();
+    let _ = hvac.tick(30);
+    let state = hvac.tick_batch(&[
+        (30, Some(HvacCommand::FanAuto(true))),
+        (30, Some(HvacCommand::Idle)),
+    ]);
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.fan_min_run_remaining(), Some(30));
+}
+
+#[test]
+fn cool_humidity_lockout_blocks_cool() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_cool_humidity_lockout(Some(30));
+    let _ = hvac.tick(0);
+    let _ = hvac.set_humidity(25);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::HumidityLockout));
+    let state = hvac.set_humidity(30);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(hvac.cool_block_reason(), None);
+    let state = hvac.set_humidity(20);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn effective_recover_reflects_adjustments() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, Some(60))
+        .with_cool(Some(100), Some(60))
+        .with_cool_post_run_off(Some(200))
+        .with_fan(None, None)
+        .with_fan_recover(Some(10), Some(20))
+        .with_proportional_recover(HvacService::Heat, 50);
+    assert_eq!(hvac.effective_heat_recover(), Some(60));
+    assert_eq!(hvac.effective_cool_recover(), Some(60));
+    assert_eq!(hvac.effective_fan_recover(), Some(10));
+    let _ = hvac.tick(60);
+    let _ = hvac.heat();
+    let _ = hvac.tick(460);
+    let _ = hvac.cool();
+    assert_eq!(hvac.effective_heat_recover(), Some(200));
+    let _ = hvac.tick(660);
+    let _ = hvac.cool();
+    let _ = hvac.tick(760);
+    let _ = hvac.idle();
+    assert_eq!(hvac.effective_cool_recover(), Some(200));
+    let _ = hvac.fan_auto(false);
+    let _ = hvac.tick(800);
+    let _ = hvac.fan_auto(true);
+    assert_eq!(hvac.effective_fan_recover(), Some(20));
+}
+
+#[test]
+fn cool_coil_overrun_until_coil_warms() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_cool_coil_overrun(150, 300);
+    let _ = hvac.tick(0);
+    let _ = hvac.set_coil_temp(80);
+    let _ = hvac.cool();
+    let _ = hvac.tick(100);
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    let state = hvac.set_coil_temp(150);
+    assert_eq!(state.fan, true);
+    let state = hvac.set_coil_temp(151);
+    assert_eq!(state.fan, false);
+    let state = hvac.set_coil_temp(100);
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn cool_coil_overrun_bounded_by_max_seconds() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_cool_coil_overrun(150, 300);
+    let _ = hvac.tick(0);
+    let _ = hvac.cool();
+    let _ = hvac.tick(100);
+    let _ = hvac.idle();
+    let state = hvac.tick(399);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(400);
+    assert_eq!(state.fan, false);
+    let _ = hvac.heat();
+    let state = hvac.idle();
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn observed_transitions_are_in_graph() {
+    assert_eq!(Hvac::states().len(), 5);
+    for state in Hvac::states() {
+        assert!(Hvac::transitions().iter().any(|edge| edge.from == *state));
+        assert!(Hvac::transitions().iter().any(|edge| edge.to == *state));
+    }
+    let mut hvac = Hvac::default()
+        .with_heat(None, Some(30))
+        .with_cool(Some(60), Some(60))
+        .with_fan(Some(20), Some(10));
+    let mut previous = hvac.logical_state();
+    let mut seen = 0;
+    for seconds in 0..2000u32 {
+        let _ = match (seconds / 50) % 8 {
+            0 => hvac.heat(),
+            1 => hvac.idle(),
+            2 => hvac.cool(),
+            3 => hvac.heat(),
+            4 => hvac.fan_auto(false),
+            5 => hvac.fan_auto(true),
+            6 => hvac.cool(),
+            _ => hvac.idle(),
+        };
+        let _ = hvac.tick(seconds);
+        let state = hvac.logical_state();
+        if state != previous {
+            assert!(
+                Hvac::transitions()
+                    .iter()
+                    .any(|edge| edge.from == previous && edge.to == state),
+                "{:?} -> {:?}",
+                previous,
+                state
+            );
+            seen += 1;
+        };
+        previous = state;
+    }
+    assert!(seen > 0);
+}
+
+#[test]
+fn min_run_grace_allows_stop_near_min_run() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(60), None)
+        .with_fan(None, None)
+        .with_min_run_grace(5);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.tick(54);
+    let state = hvac.idle();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let _ = hvac.heat();
+    let _ = hvac.tick(55);
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn blocked_seconds_accumulate_while_held_by_timer() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(10), Some(100))
+        .with_cool(None, None)
+        .with_fan(None, None);
+    let _ = hvac.tick(100);
+    let _ = hvac.heat();
+    let _ = hvac.tick(120);
+    let _ = hvac.idle();
+    let _ = hvac.tick(130);
+    let _ = hvac.heat();
+    let _ = hvac.tick(150);
+    let state = hvac.tick(220);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(hvac.heat_blocked_seconds(), 90);
+    assert_eq!(hvac.cool_blocked_seconds(), 0);
+}
+
+#[test]
+fn clamped_builders_cap_constraints() {
+    let hvac = Hvac::default()
+        .with_heat_clamped(Some(u32::MAX), Some(10))
+        .with_cool_clamped(None, Some(3601))
+        .with_fan_clamped(Some(3600), Some(u32::MAX));
+    assert_eq!(hvac.validate(), Ok(()));
+    assert_eq!(
+        hvac,
+        Hvac::default()
+            .with_heat(Some(MAX_CLAMPED_CONSTRAINT_SECONDS), Some(10))
+            .with_cool(None, Some(3600))
+            .with_fan(Some(3600), Some(3600))
+    );
+}
+
+#[test]
+fn service_opposite_and_reversing_valve() {
+    assert_eq!(HvacService::Heat.opposite(), HvacService::Cool);
+    assert_eq!(HvacService::Cool.opposite(), HvacService::Heat);
+    assert_eq!(HvacService::Heat.opposite().opposite(), HvacService::Heat);
+    assert_eq!(
+        HvacService::Cool.reversing_valve_energized(ReversingValve::O),
+        true
+    );
+    assert_eq!(
+        HvacService::Heat.reversing_valve_energized(ReversingValve::O),
+        false
+    );
+    assert_eq!(
+        HvacService::Heat.reversing_valve_energized(ReversingValve::B),
+        true
+    );
+    assert_eq!(
+        HvacService::Cool.reversing_valve_energized(ReversingValve::B),
+        false
+    );
+}
+
+#[test]
+fn temp_filter_rejects_alternating_noise() {
+    let noisy = |hvac: &mut Hvac| {
+        let _ = hvac.tick(0);
+        let _ = hvac.set_occupancy(false);
+        let _ = hvac.set_temperature(200);
+        let _ = hvac.schedule_setpoint(5000, 200);
+        let mut started = false;
+        for i in 0..20 {
+            let state = hvac.set_temperature(if i % 2 == 0 { 190 } else { 210 });
+            started |= state.service.is_some();
+        }
+        started
+    };
+    let hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_unoccupied_deadband(5);
+    let mut unfiltered = hvac;
+    assert_eq!(noisy(&mut unfiltered), true);
+    assert_eq!(noisy(&mut hvac.with_temp_filter(20)), false);
+}
+
+#[test]
+fn freeze_protection_pauses_cool_without_stopping() {
+    let mut hvac = Hvac::default()
+        .with_cool(Some(60), Some(300))
+        .with_fan(None, None)
+        .with_freeze_protection(0, 40);
+    let _ = hvac.tick(1000);
+    let state = hvac.cool();
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let _ = hvac.tick(1100);
+    let state = hvac.set_coil_temp(-5);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    assert_eq!(state.freeze_protecting, true);
+    assert_eq!(hvac.raw_outputs().cool, false);
+    let state = hvac.set_coil_temp(20);
+    assert_eq!(state.freeze_protecting, true);
+    let _ = hvac.tick(1110);
+    let state = hvac.set_coil_temp(45);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(state.freeze_protecting, false);
+    assert_eq!(HvacState::from_bits(state.to_bits()), Some(state));
+}
+
+#[test]
+fn freeze_protection_pause_is_not_runtime() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_freeze_protection(0, 40)
+        .with_capacity_warning(600);
+    let _ = hvac.tick(0);
+    let _ = hvac.cool();
+    let _ = hvac.tick(400);
+    let _ = hvac.set_coil_temp(-5);
+    let state = hvac.tick(1000);
+    assert_eq!(state.freeze_protecting, true);
+    assert_eq!(state.capacity_warning, false);
+    assert_eq!(hvac.continuous_run_seconds(HvacService::Cool), None);
+    assert_eq!(hvac.duty_cycle(HvacService::Cool, 1000), Some(40));
+    let _ = hvac.set_coil_temp(45);
+    let state = hvac.tick(1500);
+    assert_eq!(state.capacity_warning, false);
+    assert_eq!(hvac.continuous_run_seconds(HvacService::Cool), Some(500));
+    let state = hvac.tick(1600);
+    assert_eq!(state.capacity_warning, true);
+    assert_eq!(hvac.duty_cycle(HvacService::Cool, 1600), Some(62));
+}
+
+#[test]
+fn uptime_spans_first_to_last_tick() {
+    let mut hvac = Hvac::default();
+    assert_eq!(hvac.uptime_seconds(), None);
+    let _ = hvac.tick(500);
+    assert_eq!(hvac.uptime_seconds(), Some(0));
+    let state = hvac.tick(1700);
+    assert_eq!(hvac.uptime_seconds(), Some(1200));
+    let hvac = Hvac::resuming(state, 9000);
+    assert_eq!(hvac.uptime_seconds(), Some(0));
+}
+
+#[test]
+fn emergency_stop_purges_fan_then_latches_off() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(300), Some(60))
+        .with_fan(Some(300), None)
+        .with_emergency_fan_overrun(30);
+    let _ = hvac.tick(100);
+    let _ = hvac.heat();
+    let state = hvac.emergency_stop(true);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.emergency_stopped(), true);
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.heat_block_reason(), Some(BlockReason::EmergencyStop));
+    let state = hvac.tick(129);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(130);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(200);
+    assert_eq!(state.service, None);
+    let state = hvac.clear_emergency_stop();
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn emergency_stop_without_purge_is_all_off() {
+    let mut hvac = Hvac::default()
+        .with_cool(Some(300), None)
+        .with_fan(Some(300), None)
+        .with_erv(Some(300), None)
+        .with_emergency_fan_overrun(30);
+    let _ = hvac.tick(500);
+    let _ = hvac.cool();
+    let _ = hvac.erv(true);
+    let state = hvac.emergency_stop(false);
+    assert_eq!(state.is_idle(), true);
+    let state = hvac.fan_auto(false);
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn advance_to_next_event_matches_polling() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(120), Some(60))
+        .with_fan(Some(200), Some(30))
+        .with_start_stagger(5);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.tick(10);
+    let mut state = hvac.idle();
+    let mut polled = hvac;
+    let mut seconds = 10;
+    let mut events = 0;
+    while let Some((at_seconds, next)) = hvac.advance_to_next_event() {
+        assert!(at_seconds > seconds);
+        while seconds < at_seconds - 1 {
+            seconds += 1;
+            assert_eq!(polled.tick(seconds), state);
+        }
+        seconds = at_seconds;
+        assert_eq!(polled.tick(seconds), next);
+        state = next;
+        events += 1;
+    }
+    assert!(events >= 2);
+    assert_eq!(state.is_idle(), true);
+    assert_eq!(hvac.next_event_seconds(), None);
+}
+
+#[test]
+fn power_off_winds_down_and_ignores_calls() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(120), None)
+        .with_fan(Some(60), None);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.tick(60);
+    let state = hvac.power_off();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.powered, false);
+    let state = hvac.tick(120);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, false);
+    let state = hvac.fan_auto(false);
+    assert_eq!(state.fan, false);
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.heat_block_reason(), Some(BlockReason::PoweredOff));
+    assert_eq!(HvacState::from_bits(state.to_bits()), Some(state));
+    let state = hvac.power_on();
+    assert_eq!(state.powered, true);
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+}
+
+#[test]
+fn short_cycle_warning_is_advisory() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_short_cycle_warning(2, 600);
+    let _ = hvac.tick(0);
+    for seconds in [0, 100, 200] {
+        let _ = hvac.tick(seconds);
+        let state = hvac.heat();
+        assert_eq!(state.service, Some(HvacService::Heat));
+        assert_eq!(state.short_cycling, seconds == 200);
+        let _ = hvac.tick(seconds + 10);
+        let _ = hvac.idle();
+    }
+    let state = hvac.tick(600);
+    assert_eq!(state.short_cycling, true);
+    let state = hvac.tick(601);
+    assert_eq!(state.short_cycling, false);
+}
+
+#[test]
+fn short_cycle_warning_expiry_is_next_event() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_short_cycle_warning(2, 600);
+    for seconds in [0, 100, 200] {
+        let _ = hvac.tick(seconds);
+        let _ = hvac.heat();
+        let _ = hvac.tick(seconds + 10);
+        let _ = hvac.idle();
+    }
+    let state = hvac.tick(300);
+    assert_eq!(state.short_cycling, true);
+    assert_eq!(hvac.next_event_seconds(), Some(601));
+    let (seconds, state) = hvac.advance_to_next_event().unwrap();
+    assert_eq!(seconds, 601);
+    assert_eq!(state.short_cycling, false);
+    assert_eq!(hvac.next_event_seconds(), None);
+}
+
+struct MillisClock(u64);
+
+impl Clock for MillisClock {
+    fn elapsed_seconds(&self) -> u32 {
+        (self.0 / 1000) as u32
+    }
+}
+
+#[test]
+fn tick_clock_reads_time_from_clock() {
+    let mut hvac = Hvac::default().with_heat(None, Some(60));
+    let _ = hvac.tick_clock(&MillisClock(0));
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    let state = hvac.tick_clock(&MillisClock(59_999));
+    assert_eq!(state.service, None);
+    let state = hvac.tick_clock(&MillisClock(60_000));
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(hvac.tick_clock(&61u32), hvac.tick(61));
+}
+
+#[test]
+fn min_run_override_applies_to_next_run_only() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(60), None)
+        .with_fan(None, None);
+    let _ = hvac.tick(0);
+    hvac.override_next_heat_min_run(300);
+    let _ = hvac.heat();
+    let _ = hvac.tick(100);
+    let state = hvac.idle();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.tick(300);
+    assert_eq!(state.service, None);
+    let _ = hvac.heat();
+    let _ = hvac.tick(360);
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn config_round_trips_through_reconfigure() {
+    let hvac = Hvac::default();
+    assert_eq!(hvac.config(), HvacConfig::default());
+    let config = HvacConfig {
+        heat_min_run_seconds: Some(120),
+        heat_min_recover_seconds: None,
+        cool_min_run_seconds: Some(600),
+        cool_min_recover_seconds: Some(450),
+        fan_min_run_seconds: None,
+        fan_min_recover_seconds: Some(30),
+        fan_auto: false,
+    };
+    let mut reconfigured = hvac;
+    reconfigured.reconfigure(config);
+    assert_eq!(reconfigured.config(), config);
+}
+
+#[test]
+fn from_config_matches_builder() {
+    let config = HvacConfig {
+        heat_min_run_seconds: Some(120),
+        heat_min_recover_seconds: None,
+        cool_min_run_seconds: Some(600),
+        cool_min_recover_seconds: Some(450),
+        fan_min_run_seconds: None,
+        fan_min_recover_seconds: Some(30),
+        fan_auto: true,
+    };
+    let hvac = Hvac::from_config(config);
+    assert_eq!(hvac.config(), config);
+    assert_eq!(hvac.has_ticked(), false);
+    assert_eq!(
+        hvac,
+        Hvac::default()
+            .with_heat(Some(120), None)
+            .with_cool(Some(600), Some(450))
+            .with_fan(None, Some(30))
+    );
+}
+
+#[test]
+fn fan_runs_until_last_request_released() {
+    let mut hvac = Hvac::default().with_fan(None, None);
+    let _ = hvac.tick(0);
+    let ventilation = hvac.request_fan();
+    let dehumidify = hvac.request_fan();
+    assert_eq!(hvac.tick(10).fan, true);
+    let state = hvac.release_fan(ventilation);
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.logical_state(), LogicalState::FanOnly);
+    let state = hvac.release_fan(dehumidify);
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn circulate_phase_reports_time_remaining() {
+    let mut hvac = Hvac::default()
+        .with_fan(None, None)
+        .with_circulate(100, 200);
+    assert_eq!(hvac.circulate_phase(), None);
+    let state = hvac.tick(60);
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.circulate_phase(), Some(CirculatePhase::On(40)));
+    let state = hvac.tick(100);
+    assert_eq!(state.fan, false);
+    assert_eq!(hvac.circulate_phase(), Some(CirculatePhase::Off(200)));
+    assert_eq!(hvac.next_event_seconds(), Some(300));
+    let state = hvac.tick(300);
+    assert_eq!(state.fan, true);
+}
+
+#[test]
+fn capacity_warning_after_long_continuous_call() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_capacity_warning(3600);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let state = hvac.tick(3599);
+    assert_eq!(state.capacity_warning, false);
+    assert_eq!(hvac.next_event_seconds(), Some(3600));
+    let state = hvac.tick(3600);
+    assert_eq!(state.capacity_warning, true);
+    assert_eq!(hvac.continuous_run_seconds(HvacService::Heat), Some(3600));
+    assert_eq!(hvac.continuous_run_seconds(HvacService::Cool), None);
+    let state = hvac.idle();
+    assert_eq!(state.capacity_warning, false);
+}
+
+#[test]
+fn shared_compressor_recover_spans_services() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_shared_compressor_recover(120);
+    let _ = hvac.tick(1000);
+    let _ = hvac.cool();
+    let _ = hvac.tick(1100);
+    let _ = hvac.idle();
+    let _ = hvac.tick(1200);
+    let state = hvac.heat();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.heat_block_reason(), Some(BlockReason::MinRecover));
+    let state = hvac.tick(1219);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(1220);
+    assert_eq!(state.service, Some(HvacService::Heat));
+}
+
+#[test]
+fn state_diff_reports_changed_outputs() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None);
+    let idle = hvac.tick(0);
+    let heat = hvac.heat();
+    let diff = heat.diff(&idle);
+    assert_eq!(diff.service_changed, true);
+    assert_eq!(diff.fan_changed, true);
+    assert_eq!(diff.erv_changed, false);
+    let cool = hvac.cool();
+    let diff = cool.diff(&heat);
+    assert_eq!(diff.service_changed, true);
+    assert_eq!(diff.fan_changed, false);
+    assert_eq!(cool.diff(&cool).any(), false);
+    assert_eq!(cool.diff(&cool), StateDiff::default());
+}
+
+#[test]
+fn tick_interval_flags_undersampling() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(120), Some(120))
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_erv(None, None)
+        .with_tick_interval(60);
+    assert_eq!(hvac.tick(0).undersampled, false);
+    assert_eq!(hvac.tick(90).undersampled, false);
+    assert_eq!(hvac.tick(181).undersampled, true);
+    assert_eq!(hvac.heat().undersampled, true);
+    assert_eq!(hvac.tick(200).undersampled, false);
+    let mut hvac = hvac.with_heat(Some(30), Some(120));
+    assert_eq!(hvac.tick(210).undersampled, true);
+}
+
+#[test]
+fn output_mask_maps_outputs_to_fixed_bits() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_erv(None, None);
+    assert_eq!(hvac.tick(0).output_mask(), 0);
+    assert_eq!(hvac.heat().output_mask(), 0b0101);
+    assert_eq!(hvac.cool().output_mask(), 0b0110);
+    assert_eq!(hvac.erv(true).output_mask(), 0b1110);
+    let _ = hvac.idle();
+    assert_eq!(hvac.fan_auto(false).output_mask(), 0b1100);
+}
+
+#[test]
+fn startup_delay_holds_services_after_first_tick() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, Some(60))
+        .with_fan(None, None)
+        .with_startup_delay(300);
+    let _ = hvac.heat();
+    let state = hvac.tick(1000);
+    assert_eq!(state.service, None);
+    assert_eq!(state.starting_up, true);
+    assert_eq!(hvac.heat_block_reason(), Some(BlockReason::StartupDelay));
+    let state = hvac.tick(1299);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(1300);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.starting_up, false);
+}
+
+#[test]
+fn startup_delay_end_is_next_event() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, Some(100))
+        .with_fan(None, None)
+        .with_startup_delay(30);
+    let state = hvac.tick(0);
+    assert_eq!(state.starting_up, true);
+    assert_eq!(hvac.next_event_seconds(), Some(30));
+    let (seconds, state) = hvac.advance_to_next_event().unwrap();
+    assert_eq!(seconds, 30);
+    assert_eq!(state.starting_up, false);
+}
+
+#[test]
+fn max_start_latency_bounds_actual_wait() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(120), Some(60))
+        .with_cool(Some(300), Some(300))
+        .with_fan(Some(60), Some(30))
+        .with_changeover_delay(Some(90), Some(45))
+        .with_start_stagger(10);
+    let _ = hvac.tick(1000);
+    let _ = hvac.heat();
+    let _ = hvac.tick(1010);
+    assert_eq!(hvac.max_start_latency(HvacService::Heat), 0);
+    let bound = hvac.max_start_latency(HvacService::Cool);
+    assert_eq!(bound, 120 + 90 + 30 + 10);
+    let _ = hvac.cool();
+    let mut seconds = 1010;
+    while hvac.tick(seconds).service != Some(HvacService::Cool) {
+        seconds += 1;
+    }
+    assert!(seconds - 1010 <= bound);
+    let _ = hvac.power_off();
+    assert_eq!(hvac.max_start_latency(HvacService::Heat), u32::MAX);
+
+    // the fan stops with the opposing service and must recover before cool starts
+    let mut hvac = Hvac::default()
+        .with_heat(Some(100), None)
+        .with_cool(None, None)
+        .with_changeover_delay(Some(10), None);
+    let _ = hvac.tick(1000);
+    let _ = hvac.heat();
+    let _ = hvac.tick(1050);
+    let bound = hvac.max_start_latency(HvacService::Cool);
+    let _ = hvac.cool();
+    let mut seconds = 1050;
+    while hvac.tick(seconds).service != Some(HvacService::Cool) {
+        seconds += 1;
+    }
+    assert_eq!(seconds, 1160);
+    assert!(seconds - 1050 <= bound);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "strict: tick at 50 seconds is earlier than the previous tick")]
+fn strict_panics_on_decreasing_tick() {
+    let mut hvac = Hvac::default().with_strict(true);
+    let _ = hvac.tick(100);
+    let _ = hvac.tick(50);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "strict: cool called on a controller configured heat-only")]
+fn strict_panics_on_cool_when_heat_only() {
+    let mut hvac = Hvac::default().with_heat_only(true).with_strict(true);
+    let _ = hvac.tick(0);
+    let _ = hvac.cool();
+}
+
+#[test]
+fn heat_only_ignores_cool() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_heat_only(true);
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let state = hvac.cool();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let _ = hvac.idle();
+    let state = hvac.arm_service(HvacService::Cool, 0);
+    assert_eq!(state.service, None);
+    let (state, _) = hvac.test_output(Some(HvacService::Cool), false);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn lenient_tolerates_decreasing_tick() {
+    let mut hvac = Hvac::default();
+    let _ = hvac.tick(100);
+    let state = hvac.tick(50);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn commanded_fan_excludes_service_operation() {
+    let mut hvac = Hvac::default().with_heat(None, None).with_fan(None, None);
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.commanded_fan(), false);
+    let state = hvac.fan_auto(false);
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.commanded_fan(), true);
+    let _ = hvac.fan_auto(true);
+    let token = hvac.request_fan();
+    let _ = hvac.idle();
+    assert_eq!(hvac.commanded_fan(), true);
+    let state = hvac.release_fan(token);
+    assert_eq!(state.fan, false);
+    assert_eq!(hvac.commanded_fan(), false);
+}
+
+#[test]
+fn call_debounce_ignores_retracted_call() {
+    let mut hvac = Hvac::default()
+        .with_cool(Some(300), Some(300))
+        .with_fan(None, None)
+        .with_call_debounce(5);
+    let _ = hvac.tick(1000);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::CallDebounce));
+    assert_eq!(hvac.next_event_seconds(), Some(1005));
+    let _ = hvac.tick(1004);
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.cool_block_reason(), None);
+    let _ = hvac.cool();
+    let state = hvac.tick(1008);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(1009);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(hvac.take_runtime_stats().cool_cycles, 1);
+}
+
+#[test]
+fn fan_off_at_predicts_lingering_fan() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(Some(120), None);
+    let _ = hvac.tick(0);
+    assert_eq!(hvac.fan_off_at(), None);
+    let _ = hvac.heat();
+    assert_eq!(hvac.fan_off_at(), None);
+    let _ = hvac.tick(30);
+    let state = hvac.idle();
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.fan_off_at(), Some(120));
+    let _ = hvac.fan_auto(false);
+    assert_eq!(hvac.fan_off_at(), None);
+    let _ = hvac.fan_auto(true);
+    let state = hvac.tick(120);
+    assert_eq!(state.fan, false);
+    assert_eq!(hvac.fan_off_at(), None);
+}
+
+#[test]
+fn valve_settle_sequences_valve_before_compressor() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_valve_settle(5);
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.valve_settling, false);
+    let _ = hvac.tick(10);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(state.valve_settling, true);
+    assert_eq!(hvac.valve_service(), Some(HvacService::Cool));
+    assert_eq!(hvac.last_service(), Some(HvacService::Heat));
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::ValveSettle));
+    assert_eq!(hvac.next_event_seconds(), Some(15));
+    let state = hvac.tick(14);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(15);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(state.valve_settling, false);
+}
+
+#[test]
+fn binding_constraint_reports_longest_gate() {
+    let mut hvac = Hvac::default()
+        .with_heat(Some(300), None)
+        .with_cool(None, Some(300))
+        .with_fan(None, None)
+        .with_changeover_delay(Some(90), None);
+    let _ = hvac.tick(100);
+    assert_eq!(
+        hvac.binding_constraint(HvacService::Cool),
+        Some((ConstraintKind::MinRecover, 200))
+    );
+    let _ = hvac.heat();
+    assert_eq!(hvac.binding_constraint(HvacService::Heat), None);
+    assert_eq!(
+        hvac.binding_constraint(HvacService::Cool),
+        Some((ConstraintKind::OpposingMinRun, 300))
+    );
+    let _ = hvac.tick(250);
+    assert_eq!(
+        hvac.binding_constraint(HvacService::Cool),
+        Some((ConstraintKind::OpposingMinRun, 150))
+    );
+    let _ = hvac.tick(400);
+    let _ = hvac.idle();
+    assert_eq!(
+        hvac.binding_constraint(HvacService::Cool),
+        Some((ConstraintKind::ChangeoverDelay, 90))
+    );
+    let _ = hvac.tick(490);
+    assert_eq!(hvac.binding_constraint(HvacService::Cool), None);
+}
+
+#[test]
+fn fan_continuous_on_changeover_holds_fan() {
+    let build = |continuous| {
+        Hvac::default()
+            .with_heat(None, None)
+            .with_cool(None, None)
+            .with_fan(None, Some(60))
+            .with_changeover_delay(Some(30), None)
+            .with_fan_continuous_on_changeover(continuous)
+    };
+    let mut hvac = build(false);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.tick(100);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, false);
+
+    let mut hvac = build(true);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.tick(100);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.fan_off_at(), None);
+    let state = hvac.tick(129);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(130);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(state.fan, true);
+}
+
+#[test]
+fn min_call_duration_applies_per_service() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_min_call_duration(HvacService::Cool, 120);
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let _ = hvac.tick(10);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::CallDebounce));
+    let state = hvac.tick(129);
+    assert_eq!(state.service, None);
+    let state = hvac.tick(130);
+    assert_eq!(state.service, Some(HvacService::Cool));
+}
+
+#[test]
+fn checksum_is_stable_and_tracks_state() {
+    let mut hvac = Hvac::default();
+    assert_eq!(hvac.checksum(), 0x7497_f0b7);
+    assert_eq!(hvac.checksum(), Hvac::default().checksum());
+    let _ = hvac.tick(100);
+    let ticked = hvac.checksum();
+    assert_ne!(ticked, Hvac::default().checksum());
+    let _ = hvac.heat();
+    let heating = hvac.checksum();
+    assert_ne!(heating, ticked);
+    let _ = hvac.arm_service(HvacService::Cool, 60);
+    let armed = hvac.checksum();
+    assert_ne!(armed, heating);
+    // a repeated tick only updates bookkeeping recomputed on every tick
+    let _ = hvac.tick(100);
+    assert_eq!(hvac.checksum(), armed);
+    let default = Hvac::default().checksum();
+    assert_ne!(Hvac::default().with_call_debounce(30).checksum(), default);
+    assert_ne!(Hvac::default().with_valve_settle(5).checksum(), default);
+    assert_ne!(Hvac::default().with_fan_on_heat(false).checksum(), default);
+    assert_ne!(
+        Hvac::default()
+            .with_conflict_policy(ConflictPolicy::Ignore)
+            .checksum(),
+        default
+    );
+}
+
+#[test]
+fn fan_coupled_per_service_across_changeover() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, Some(60))
+        .with_heat_fan_coupled(false)
+        .with_cool_fan_coupled(true);
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.fan, false);
+    let _ = hvac.tick(100);
+    let state = hvac.cool();
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(state.fan, true);
+    let _ = hvac.tick(200);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.fan, false);
+    let _ = hvac.tick(230);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::FanMinRecover));
+    let state = hvac.tick(260);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(state.fan, true);
+
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_cool_fan_coupled(false);
+    let _ = hvac.tick(0);
+    let state = hvac.cool();
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn available_at_reports_recover_end() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, Some(60))
+        .with_cool(None, Some(300))
+        .with_fan(None, Some(30));
+    let _ = hvac.tick(1000);
+    assert_eq!(hvac.heat_available_at(), None);
+    let _ = hvac.heat();
+    assert_eq!(hvac.heat_available_at(), None);
+    assert_eq!(hvac.fan_available_at(), None);
+    let _ = hvac.tick(1100);
+    let _ = hvac.idle();
+    assert_eq!(hvac.heat_available_at(), Some(1160));
+    assert_eq!(hvac.fan_available_at(), Some(1130));
+    assert_eq!(hvac.cool_available_at(), None);
+    let _ = hvac.tick(1160);
+    assert_eq!(hvac.heat_available_at(), None);
+}
+
+#[test]
+fn service_fan_config_purges_and_prepurges() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_heat_fan_config(ServiceFanConfig {
+            coupled: true,
+            purge_seconds: 90,
+            prepurge_seconds: 0,
+        })
+        .with_cool_fan_config(ServiceFanConfig {
+            coupled: true,
+            purge_seconds: 0,
+            prepurge_seconds: 20,
+        });
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let _ = hvac.tick(100);
+    let state = hvac.idle();
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.fan_off_at(), Some(190));
+    let state = hvac.tick(189);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(190);
+    assert_eq!(state.fan, false);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::StartStagger));
+    let state = hvac.tick(210);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let _ = hvac.tick(300);
+    let state = hvac.idle();
+    assert_eq!(state.fan, false);
+}
+
+#[test]
+fn changeover_gap_distinguished_from_idle() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_changeover_delay(Some(90), None);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    assert_eq!(hvac.in_changeover_gap(), false);
+    let _ = hvac.tick(100);
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.in_changeover_gap(), false);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.in_changeover_gap(), true);
+    let state = hvac.tick(190);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    assert_eq!(hvac.in_changeover_gap(), false);
+}
+
+#[test]
+fn restore_rewinds_to_snapshot() {
+    let mut hvac = Hvac::default().with_heat(None, None).with_fan(None, None);
+    let _ = hvac.tick(100);
+    let snapshot = hvac.snapshot();
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    let _ = hvac.tick(200);
+    hvac.restore(snapshot);
+    assert_eq!(hvac, snapshot);
+    assert_eq!(hvac.uptime_seconds(), Some(0));
+    let state = hvac.tick(100);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn auto_zero_on_first_call_seeds_time() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_startup_delay(60)
+        .with_auto_zero_on_first_call(true);
+    let state = hvac.heat();
+    assert_eq!(hvac.has_ticked(), true);
+    assert_eq!(state.service, None);
+    assert_eq!(state.starting_up, true);
+    let state = hvac.tick(60);
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(hvac.uptime_seconds(), Some(60));
+
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(None, None)
+        .with_startup_delay(60);
+    let _ = hvac.heat();
+    assert_eq!(hvac.has_ticked(), false);
+    let state = hvac.tick(60);
+    assert_eq!(state.service, None);
+}
+
+#[test]
+fn tick_with_runtime_reports_interval_runtime() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_fan(Some(60), None);
+    let (_, delta) = hvac.tick_with_runtime(100);
+    assert_eq!(delta, RuntimeDelta::default());
+    let _ = hvac.heat();
+    let (_, delta) = hvac.tick_with_runtime(130);
+    assert_eq!(delta.heat_seconds, 30);
+    assert_eq!(delta.cool_seconds, 0);
+    assert_eq!(delta.fan_seconds, 30);
+    let _ = hvac.idle();
+    let (state, delta) = hvac.tick_with_runtime(150);
+    assert_eq!(state.fan, true);
+    assert_eq!(delta.heat_seconds, 0);
+    assert_eq!(delta.fan_seconds, 20);
+    let (_, delta) = hvac.tick_with_runtime(150);
+    assert_eq!(delta, RuntimeDelta::default());
+}
+
+#[test]
+fn alternating_calls_at_fixed_time_converge() {
+    let configs = [
+        Hvac::default()
+            .with_heat(Some(60), Some(60))
+            .with_cool(Some(300), Some(300))
+            .with_fan(Some(30), Some(30))
+            .with_changeover_delay(Some(90), Some(90))
+            .with_auto_hold(120),
+        Hvac::default()
+            .with_heat(None, None)
+            .with_cool(None, None)
+            .with_fan(None, None),
+        Hvac::default()
+            .with_heat(None, Some(60))
+            .with_cool(None, Some(60))
+            .with_fan(None, Some(60))
+            .with_start_stagger(5)
+            .with_valve_settle(5)
+            .with_call_debounce(5),
+    ];
+    for &config in configs.iter() {
+        let mut hvac = config;
+        let _ = hvac.tick(1000);
+        let _ = hvac.heat();
+        let _ = hvac.tick(1030);
+        let _ = (hvac.cool(), hvac.heat());
+        let first = (hvac.cool(), hvac.heat());
+        for _ in 0..100 {
+            assert_eq!((hvac.cool(), hvac.heat()), first);
+        }
+        let settled = hvac.heat();
+        for _ in 0..10 {
+            assert_eq!(hvac.tick(1030), settled);
+            assert_eq!(hvac.evaluate(1030), settled);
+        }
+    }
+}
+
+#[test]
+fn humidity_modulates_fan_speed_while_cooling() {
+    let mut hvac = Hvac::default()
+        .with_cool(None, None)
+        .with_fan(None, None)
+        .with_humidity_fan_modulation(100, 200, 60);
+    let _ = hvac.tick(0);
+    let _ = hvac.cool();
+    assert_eq!(hvac.fan_speed(), u8::MAX);
+    let _ = hvac.set_humidity(70);
+    assert_eq!(hvac.fan_speed(), 100);
+    let _ = hvac.set_humidity(60);
+    assert_eq!(hvac.fan_speed(), 100);
+    let _ = hvac.set_humidity(59);
+    assert_eq!(hvac.fan_speed(), 200);
+    let _ = hvac.idle();
+    let _ = hvac.fan_auto(false);
+    assert_eq!(hvac.fan_speed(), u8::MAX);
+}
+
+#[test]
+fn clear_service_calls_keeps_armed_call_and_fan() {
+    let build = || {
+        let mut hvac = Hvac::default()
+            .with_heat(None, None)
+            .with_cool(None, None)
+            .with_fan(None, None)
+            .with_cool_fan_config(ServiceFanConfig {
+                prepurge_seconds: 60,
+                ..ServiceFanConfig::default()
+            });
+        let _ = hvac.tick(0);
+        let _ = hvac.heat();
+        let _ = hvac.arm_service(HvacService::Cool, 60);
+        hvac
+    };
+    let mut hvac = build();
+    let state = hvac.clear_service_calls();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
+    let state = hvac.tick(60);
+    assert_eq!(state.service, Some(HvacService::Cool));
+
+    let mut hvac = build();
+    let state = hvac.idle();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, false);
+    let state = hvac.tick(60);
+    assert_eq!(state.service, None);
+
+    let mut hvac = build();
+    let _ = hvac.fan_auto(false);
+    let _ = hvac.tick(60);
+    let state = hvac.clear_service_calls();
+    assert_eq!(state.service, None);
+    assert_eq!(state.fan, true);
 }
@@ -0,0 +1,81 @@
+#![cfg(all(
+    not(feature = "fan"),
+    feature = "cool",
+    feature = "erv",
+    feature = "stats"
+))]
+
+use hvac::prelude::*;
+
+#[test]
+fn heat_and_cool_without_fan() {
+    let mut hvac = Hvac::default()
+        .with_heat(None, None)
+        .with_cool(Some(300), Some(60));
+    let _ = hvac.tick(0);
+    let state = hvac.heat();
+    assert_eq!(state.service, Some(HvacService::Heat));
+    assert_eq!(state.operating_mode(), OperatingMode::Heating);
+    let state = hvac.cool();
+    assert_eq!(state.service, None);
+    assert_eq!(hvac.cool_block_reason(), Some(BlockReason::MinRecover));
+    let state = hvac.tick(60);
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let state = hvac.idle();
+    assert_eq!(state.service, Some(HvacService::Cool));
+    let state = hvac.tick(360);
+    assert_eq!(state.service, None);
+    assert!(state.is_idle());
+}
+
+#[test]
+fn pending_waits_without_fan() {
+    let mut hvac = Hvac::default().with_erv(None, None);
+    let _ = hvac.tick(0);
+    let mut waits = hvac.pending_waits();
+    assert_eq!(waits.next(), Some((TimerKind::Heat, 60)));
+    assert_eq!(waits.next(), Some((TimerKind::Cool, 300)));
+    assert_eq!(waits.next(), None);
+}
+
+#[test]
+fn state_bits_reserve_fan_without_fan() {
+    assert_eq!(HvacState::from_bits(0b100), None);
+    assert_eq!(HvacState::from_bits(0b1000), None);
+    assert_eq!(HvacState::from_bits(0b1000_0000), None);
+    let state = HvacState {
+        service: Some(HvacService::Heat),
+        erv: true,
+        powered: true,
+        short_cycling: false,
+        capacity_warning: false,
+        undersampled: false,
+        starting_up: false,
+        valve_settling: false,
+    };
+    assert_eq!(HvacState::from_bits(state.to_bits()), Some(state));
+}
+
+#[test]
+fn graph_without_fan() {
+    assert_eq!(
+        Hvac::states(),
+        &[
+            LogicalState::Idle,
+            LogicalState::Heating,
+            LogicalState::Cooling
+        ]
+    );
+    assert_eq!(Hvac::transitions().len(), 6);
+}
+
+#[test]
+fn emergency_stop_without_fan() {
+    let mut hvac = Hvac::default().with_heat(Some(300), None);
+    let _ = hvac.tick(0);
+    let _ = hvac.heat();
+    let state = hvac.emergency_stop();
+    assert_eq!(state.service, None);
+    let state = hvac.clear_emergency_stop();
+    assert_eq!(state.service, None);
+}